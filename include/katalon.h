@@ -0,0 +1,128 @@
+/*
+ * The C interface of the katalon engine, see src/ffi.rs.
+ *
+ * Boards and solvers are opaque handles, created and freed by the library.
+ * The functions which can fail return a status and write their results to
+ * the given pointers. Moves are a square and a cell, both from 0 to 4.
+ * Evaluations are from the perspective of the player onturn. A panic inside
+ * the library is reported as KATALON_FAILED, or NULL or -1 for the functions
+ * which do not return a status.
+ *
+ * The searches take a timeout in milliseconds, 0 times out at once and
+ * UINT64_MAX searches without a time limit.
+ */
+#ifndef KATALON_H
+#define KATALON_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* The most moves a position can have. */
+#define KATALON_MAX_MOVES 25
+
+typedef struct KatalonBoard KatalonBoard;
+typedef struct KatalonSolver KatalonSolver;
+
+typedef struct {
+    uint8_t square;
+    uint8_t cell;
+} KatalonMove;
+
+typedef struct {
+    /* -1 for a loss, 0 for a draw and 1 for a win. */
+    int32_t result;
+    /* The number of moves until the game is over. */
+    int32_t distance;
+} KatalonEval;
+
+typedef enum {
+    KATALON_OK = 0,
+    /* A null pointer or text which is not UTF-8. */
+    KATALON_INVALID_ARGUMENT = 1,
+    KATALON_NOTATION = 2,
+    KATALON_MOVE = 3,
+    KATALON_POSITION = 4,
+    KATALON_GAME_OVER = 5,
+    KATALON_TIMEOUT = 6,
+    KATALON_FAILED = 7,
+} KatalonStatus;
+
+/* The version of the library, owned by the library. */
+const char *katalon_version(void);
+
+/* A description of the status, owned by the library, "unknown status" for
+ * values which are not a KatalonStatus. */
+const char *katalon_status_message(int32_t status);
+
+/* Create the empty board. */
+KatalonBoard *katalon_board_new(void);
+
+/* Create a board from the moves played so far, e.g. "0020". */
+KatalonStatus katalon_board_load(const char *moves, KatalonBoard **out);
+
+/* Create a board from the position notation. */
+KatalonStatus katalon_board_from_fen(const char *fen, KatalonBoard **out);
+
+/* Copy the board, the copy has to be freed as well. */
+KatalonBoard *katalon_board_clone(const KatalonBoard *board);
+
+/* Free the board, freeing NULL does nothing. */
+void katalon_board_free(KatalonBoard *board);
+
+/* The position notation of the board, free it with katalon_string_free(). */
+char *katalon_board_to_fen(const KatalonBoard *board);
+
+/* Free a string returned by the library, freeing NULL does nothing. */
+void katalon_string_free(char *string);
+
+/*
+ * Write at most capacity legal moves to the buffer and return how many legal
+ * moves there are, which is 0 if the game is over.
+ */
+size_t katalon_board_moves(const KatalonBoard *board, KatalonMove *buffer, size_t capacity);
+
+/*
+ * Play the move if it can be made, a square or cell above 4 gives
+ * KATALON_INVALID_ARGUMENT.
+ */
+KatalonStatus katalon_board_play(KatalonBoard *board, KatalonMove move);
+
+/* 0 if the game is not over, 1 or 2 if that player won, 3 for a draw. */
+int32_t katalon_board_isover(const KatalonBoard *board);
+
+/* The player onturn, 1 or 2. */
+int32_t katalon_board_onturn(const KatalonBoard *board);
+
+/* Create a solver with a table of the given number of megabytes, the table
+ * is kept between searches. */
+KatalonSolver *katalon_solver_new(size_t megabytes);
+
+/* Free the solver, freeing NULL does nothing. */
+void katalon_solver_free(KatalonSolver *solver);
+
+/*
+ * Evaluate the board within the timeout in milliseconds, 0 times out at once
+ * and UINT64_MAX searches without a time limit.
+ */
+KatalonStatus katalon_eval(KatalonSolver *solver, const KatalonBoard *board,
+                           uint64_t timeout_ms, KatalonEval *out);
+
+/*
+ * Search the best moves within the timeout in milliseconds, which is limited
+ * like the timeout of katalon_eval(). At most capacity of them are written to
+ * the buffer, count gets how many there are and out their evaluation, both
+ * may be NULL.
+ */
+KatalonStatus katalon_bestmoves(KatalonSolver *solver, const KatalonBoard *board,
+                                uint64_t timeout_ms, KatalonMove *buffer, size_t capacity,
+                                size_t *count, KatalonEval *out);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* KATALON_H */
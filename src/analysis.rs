@@ -0,0 +1,270 @@
+use crate::{board, error, solver, stats::search};
+use serde_json::{json, Value};
+
+// The analysis requests are JSON objects, one per line, e.g.
+//   {"id": 1, "op": "eval", "moves": "0020", "timeout_ms": 5000}
+//
+// id: optional, copied to the response to match it with the request
+// op: "eval" (default), "bestmoves", "eval_all" or "pv"
+// moves: the moves played so far, see Board::load(), or
+// fen: the position notation, see Board::from_fen()
+// timeout_ms, nodes, depth: optional limits of the search, see SearchLimits
+//
+// Every request is answered with one JSON object on a single line, with
+// either the result and the stats of the search or an error, which are
+// serialized like their types, e.g. search::Stats. The searches share the
+// table of the session, so the count of the table stats includes the entries
+// of the earlier searches.
+
+/// Answer a single request line with a single response line, searching with
+/// the table of the session.
+pub fn handle(session: &mut solver::Session, line: &str) -> String {
+    let request: Value = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(error) => {
+            let error = error::Error::Format(format!("Invalid JSON: {}.", error));
+            return respond(&Value::Null, Err(error), None).to_string();
+        }
+    };
+
+    match answer(session, &request) {
+        Ok(response) => response.to_string(),
+        Err(error) => {
+            let id = request.get("id").cloned().unwrap_or(Value::Null);
+            respond(&id, Err(error), None).to_string()
+        }
+    }
+}
+
+/// Answer a request with the result and the stats of the search, this fails
+/// early if the request itself is invalid.
+pub fn answer(session: &mut solver::Session, request: &Value) -> error::Result<Value> {
+    Ok(Request::parse(request)?.answer(session))
+}
+
+/// The search asked by a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eval,
+    Bestmoves,
+    EvalAll,
+    Pv,
+}
+
+/// A request which is read and checked, so it only has to be searched.
+#[derive(Debug, Clone)]
+pub struct Request {
+    id: Value,
+    op: Op,
+    board: board::Board,
+    limits: solver::SearchLimits,
+}
+
+impl Request {
+    /// Read the request, which fails if it is invalid.
+    pub fn parse(request: &Value) -> error::Result<Self> {
+        let op = match request.get("op") {
+            None => "eval",
+            Some(op) => op
+                .as_str()
+                .ok_or_else(|| error::Error::Format(String::from("The op should be a string.")))?,
+        };
+        let op = match op {
+            "eval" => Op::Eval,
+            "bestmoves" => Op::Bestmoves,
+            "eval_all" => Op::EvalAll,
+            "pv" => Op::Pv,
+            op => {
+                return Err(error::Error::Format(format!(
+                    "Unknown op {}, use eval, bestmoves, eval_all or pv.",
+                    op
+                )))
+            }
+        };
+
+        Ok(Self {
+            id: request.get("id").cloned().unwrap_or(Value::Null),
+            op,
+            board: position(request)?,
+            limits: limits(request)?,
+        })
+    }
+
+    /// Search for at most the given time, even if the request allows more.
+    pub fn limit_time(&mut self, time: std::time::Duration) {
+        self.limits.time = Some(self.limits.time.map_or(time, |limit| limit.min(time)));
+    }
+
+    /// Answer the request with the result and the stats of the search.
+    pub fn answer(&self, session: &mut solver::Session) -> Value {
+        let (board, limits) = (&self.board, self.limits.clone());
+        let (result, stats) = match self.op {
+            Op::Eval => {
+                let (result, stats) = session.eval(board, limits);
+                (result.map(|value| json!({ "eval": value })), stats)
+            }
+            Op::Bestmoves => {
+                let (result, stats) = session.bestmoves(board, limits);
+                let result = result
+                    .map(|(value, moves)| json!({ "eval": value, "moves": moves_json(&moves) }));
+                (result, stats)
+            }
+            Op::EvalAll => {
+                let (result, stats) = session.eval_all(board, limits);
+                let result = result.map(|evaluations| {
+                    let moves: Vec<Value> = evaluations
+                        .iter()
+                        .map(|(mv, value)| json!({ "move": mv.to_string(), "eval": value }))
+                        .collect();
+                    json!({ "moves": moves })
+                });
+                (result, stats)
+            }
+            Op::Pv => {
+                let (result, stats) = session.pv(board, limits);
+                let result = result
+                    .map(|(value, moves)| json!({ "eval": value, "moves": moves_json(&moves) }));
+                (result, stats)
+            }
+        };
+        respond(&self.id, result, Some(&stats))
+    }
+}
+
+/// Read the position of the request.
+fn position(request: &Value) -> error::Result<board::Board> {
+    match (request.get("moves"), request.get("fen")) {
+        (Some(Value::String(moves)), None) => board::Board::load(moves),
+        (None, Some(Value::String(fen))) => board::Board::from_fen(fen),
+        (None, None) => Ok(board::Board::new()),
+        _ => Err(error::Error::Format(String::from(
+            "Give the position as a string of either moves or fen.",
+        ))),
+    }
+}
+
+/// Read the limits of the request.
+fn limits(request: &Value) -> error::Result<solver::SearchLimits> {
+    let number = |name: &str| -> error::Result<Option<u64>> {
+        match request.get(name) {
+            None => Ok(None),
+            Some(value) => value.as_u64().map(Some).ok_or_else(|| {
+                error::Error::Format(format!("The {} should be a positive integer.", name))
+            }),
+        }
+    };
+
+    let mut limits = solver::SearchLimits::new();
+    if let Some(ms) = number("timeout_ms")? {
+        limits = limits.time(std::time::Duration::from_millis(ms));
+    }
+    if let Some(nodes) = number("nodes")? {
+        limits = limits.nodes(nodes as usize);
+    }
+    if let Some(depth) = number("depth")? {
+        limits = limits.depth(depth.min(i16::MAX as u64) as i16);
+    }
+    Ok(limits)
+}
+
+/// Build the response from the result of the analysis.
+pub fn respond(id: &Value, result: error::Result<Value>, stats: Option<&search::Stats>) -> Value {
+    let mut response = match result {
+        Ok(value) => value,
+        Err(error) => json!({
+            "error": { "kind": error_kind(&error), "message": error.to_string() }
+        }),
+    };
+    response["id"] = id.clone();
+    if let Some(stats) = stats {
+        response["stats"] = json!(stats);
+    }
+    response
+}
+
+/// The kind of error as a short name, so it can be matched on.
+fn error_kind(error: &error::Error) -> &'static str {
+    match error {
+        error::Error::Notation(_) => "notation",
+        error::Error::Move(board::MoveError::GameOver) => "gameover",
+        error::Error::Move(_) => "move",
+        error::Error::Position(_) => "position",
+        error::Error::Rules(_) => "rules",
+        error::Error::Timeout => "timeout",
+        error::Error::Cancelled => "cancelled",
+        error::Error::NodeLimit => "nodes",
+        error::Error::DepthLimit => "depth",
+        error::Error::Io(_) => "io",
+        error::Error::Format(_) => "format",
+    }
+}
+
+fn moves_json(moves: &[board::Move]) -> Value {
+    moves.iter().map(|mv| mv.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Answer a request and parse the response.
+    fn ask(request: &str) -> Value {
+        let mut session = solver::Session::new(1_000_000);
+        let response = handle(&mut session, request);
+        assert_eq!(response.lines().count(), 1);
+        serde_json::from_str(&response).unwrap()
+    }
+
+    /// Test whether invalid requests are answered with an error.
+    #[test]
+    fn invalid() {
+        let response = ask("{\"id\": 3, \"moves\": \"5\"}");
+        assert_eq!(response["id"], 3);
+        assert_eq!(response["error"]["kind"], "notation");
+        assert!(response.get("stats").is_none());
+
+        assert_eq!(ask("{\"op\": \"jump\"}")["error"]["kind"], "format");
+        assert_eq!(ask("{\"timeout_ms\": -1}")["error"]["kind"], "format");
+        assert_eq!(
+            ask("{\"moves\": \"\", \"fen\": \"\"}")["error"]["kind"],
+            "format"
+        );
+        assert_eq!(ask("not json")["error"]["kind"], "format");
+        assert_eq!(ask("not json")["id"], Value::Null);
+    }
+
+    /// Test whether errors of the search are answered with the stats.
+    #[test]
+    fn search_errors() {
+        let response = ask("{\"op\": \"bestmoves\", \"moves\": \"2320212422\"}");
+        assert_eq!(response["error"]["kind"], "gameover");
+
+        let response = ask("{\"id\": \"a\", \"moves\": \"0123\", \"nodes\": 1000}");
+        assert_eq!(response["id"], "a");
+        assert_eq!(response["error"]["kind"], "nodes");
+        assert_eq!(response["stats"]["visited"], 1000);
+        assert_eq!(response["stats"]["stop"], "nodes");
+    }
+
+    /// Test whether the operations answer with evaluations and moves.
+    #[test]
+    fn operations() {
+        let moves = "12104023441433032242301121";
+
+        let response = ask(&format!("{{\"moves\": \"{}\"}}", moves));
+        assert_eq!(response["eval"], json!({ "result": "win", "distance": 2 }));
+        assert!(response["stats"]["visited"].as_u64().unwrap() > 0);
+
+        let response = ask(&format!("{{\"op\": \"pv\", \"moves\": \"{}\"}}", moves));
+        assert_eq!(response["moves"].as_array().unwrap().len(), 2);
+
+        let response = ask(&format!(
+            "{{\"op\": \"eval_all\", \"moves\": \"{}\"}}",
+            moves
+        ));
+        let evaluations = response["moves"].as_array().unwrap();
+        assert!(evaluations
+            .iter()
+            .any(|evaluation| evaluation["eval"] == json!({ "result": "win", "distance": 2 })));
+    }
+}
@@ -0,0 +1,194 @@
+use crate::{board, error, eval, record, solver};
+
+// A game is annotated by evaluating all the possible moves before every move
+// of the game. A move which gives away the result of the best move, e.g. a
+// draw or a loss where a win was possible, is a blunder. A move which keeps
+// the result but wins slower or loses faster is an inaccuracy. Both get a
+// comment naming the best alternatives, e.g.
+//
+//   5. 21 {[%eval loss 4] blunder, best 20 or 24 (win 3)}
+//
+// The positions are searched from the last move to the first, so the
+// searches of the earlier positions, which take longer, find the later ones
+// in the table of the session.
+
+/// How a move compares to the best move of the position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Judgement {
+    Best,
+    /// The move keeps the result, but wins slower or loses faster.
+    Inaccuracy,
+    /// The move gives away the result of the best move.
+    Blunder,
+}
+
+impl Judgement {
+    /// Judge the evaluation of the move against the best evaluation, both
+    /// from the perspective of the player who moves.
+    pub fn of(played: eval::Eval, best: eval::Eval) -> Self {
+        if played.result() < best.result() {
+            Judgement::Blunder
+        } else if played < best && played.result() != eval::Result::Draw {
+            // The distance of a draw says nothing about the quality of the move.
+            Judgement::Inaccuracy
+        } else {
+            Judgement::Best
+        }
+    }
+}
+
+impl std::fmt::Display for Judgement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Judgement::Best => "best",
+                Judgement::Inaccuracy => "inaccuracy",
+                Judgement::Blunder => "blunder",
+            }
+        )
+    }
+}
+
+/// Annotate the moves of the record with their evaluation, and comment on
+/// the blunders and inaccuracies. Every position is searched within the
+/// limits, the judgements are None for the moves of which the search did not
+/// finish.
+pub fn annotate(
+    session: &mut solver::Session,
+    record: &record::Record,
+    limits: impl Into<solver::SearchLimits>,
+) -> error::Result<(record::Record, Vec<Option<Judgement>>)> {
+    let limits = limits.into();
+    // Check the moves before spending any time on searching.
+    record.board()?;
+
+    let mut boards = Vec::with_capacity(record.moves.len());
+    let mut board = board::Board::with_rules(record.rules);
+    for annotated in &record.moves {
+        boards.push(board.clone());
+        board.play(annotated.mv.square, annotated.mv.cell);
+    }
+
+    let mut annotated = record.clone();
+    let mut judgements = vec![None; record.moves.len()];
+    for (index, board) in boards.iter().enumerate().rev() {
+        let annotation = &mut annotated.moves[index];
+        let evaluations = match session.eval_all(board, limits.clone()).0 {
+            Ok(evaluations) => evaluations,
+            Err(_) => continue,
+        };
+        let played = match played(session, board, annotation.mv, &evaluations, &limits) {
+            Some(played) => played,
+            None => continue,
+        };
+
+        let best = evaluations
+            .iter()
+            .map(|&(_, value)| value)
+            .max()
+            .expect("The game is not over, so there are moves.");
+        let judgement = Judgement::of(played, best);
+        judgements[index] = Some(judgement);
+        annotation.eval = Some(played);
+
+        if judgement != Judgement::Best {
+            let alternatives: Vec<String> = evaluations
+                .iter()
+                .filter(|&&(_, value)| value == best)
+                .map(|(mv, _)| mv.to_string())
+                .collect();
+            let (result, distance) = best.human();
+            let comment = format!(
+                "{}, best {} ({} {})",
+                judgement,
+                alternatives.join(" or "),
+                result,
+                distance
+            );
+            annotation.comment = Some(match &annotation.comment {
+                Some(text) => format!("{} {}", text, comment),
+                None => comment,
+            });
+        }
+    }
+    Ok((annotated, judgements))
+}
+
+/// The evaluation of the played move from the perspective of the player who
+/// made it, or None if the search did not finish.
+fn played(
+    session: &mut solver::Session,
+    board: &board::Board,
+    mv: board::Move,
+    evaluations: &[(board::Move, eval::Eval)],
+    limits: &solver::SearchLimits,
+) -> Option<eval::Eval> {
+    if let Some(&(_, value)) = evaluations.iter().find(|&&(other, _)| other == mv) {
+        return Some(value);
+    }
+
+    // Only one move of every symmetry is evaluated for the first move, so
+    // the played move is searched on its own, counting its distance from the
+    // board before it.
+    let mut after = board.clone();
+    after.play(mv.square, mv.cell);
+    let value = session.eval(&after, limits.clone()).0.ok()?;
+    Some((-value).absolute(board.movecount(), after.movecount()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test whether moves are judged by the result and distance they give away.
+    #[test]
+    fn judge() {
+        let win = |distance| eval::Eval::from(eval::Result::Win, distance);
+        let draw = eval::Eval::from(eval::Result::Draw, 0);
+        let loss = |distance| eval::Eval::from(eval::Result::Loss, distance);
+
+        assert_eq!(Judgement::of(win(3), win(3)), Judgement::Best);
+        assert_eq!(Judgement::of(win(5), win(3)), Judgement::Inaccuracy);
+        assert_eq!(Judgement::of(loss(2), loss(6)), Judgement::Inaccuracy);
+        assert_eq!(Judgement::of(draw, win(3)), Judgement::Blunder);
+        assert_eq!(Judgement::of(loss(9), draw), Judgement::Blunder);
+        assert_eq!(
+            Judgement::of(eval::Eval::from(eval::Result::Draw, -4), draw),
+            Judgement::Best
+        );
+    }
+
+    /// Test whether the mistakes of a game are annotated with the best moves.
+    #[test]
+    fn annotation() {
+        let mut session = solver::Session::new(1_000_000);
+        let record: record::Record = "1. 23 32 2. 20 02 3. 21 12 4. 24 42 5. 22 1-0"
+            .parse()
+            .unwrap();
+        // The searches of the first positions do not finish within the limit.
+        let limits = solver::SearchLimits::new().nodes(200_000);
+        let (annotated, judgements) = annotate(&mut session, &record, limits).unwrap();
+
+        use Judgement::*;
+        assert_eq!(judgements[..5], [None; 5]);
+        assert_eq!(
+            judgements[5..],
+            [Some(Blunder), Some(Best), Some(Inaccuracy), Some(Best)]
+        );
+
+        let moves = &annotated.moves;
+        assert_eq!(moves[4].eval, None);
+        assert_eq!(moves[7].eval, Some(eval::Eval::from(eval::Result::Loss, 2)));
+        assert_eq!(
+            moves[7].comment.as_deref(),
+            Some("inaccuracy, best 41 or 43 (loss 8)")
+        );
+        assert_eq!(moves[8].comment, None);
+        assert_eq!(
+            annotated.to_string().parse::<record::Record>().unwrap(),
+            annotated
+        );
+    }
+}
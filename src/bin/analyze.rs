@@ -0,0 +1,27 @@
+use katalon::{analysis, solver};
+use std::io::{BufRead, Write};
+
+// Usage: analyze
+//   e.g. echo '{"op": "bestmoves", "moves": "0020", "timeout_ms": 5000}' | analyze
+// Reads one JSON request per line from stdin and writes one JSON response per
+// line to stdout, see the analysis module for the format.
+
+fn main() {
+    // The table is kept between the requests, which often analyse related positions.
+    let mut session = solver::Session::from_gb(1.0);
+    let stdout = std::io::stdout();
+    for line in std::io::stdin().lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let mut stdout = stdout.lock();
+        writeln!(stdout, "{}", analysis::handle(&mut session, &line))
+            .expect("Could not write the response.");
+        stdout.flush().expect("Could not write the response.");
+    }
+}
@@ -0,0 +1,83 @@
+use katalon::{annotate, record, solver};
+
+// Usage: annotate <records> [timeout_ms] [game]
+//   e.g. annotate games.txt 10000 > annotated.txt
+// Annotates the games of the records file, or only the given game counting
+// from 1, and writes the annotated records to stdout. Every position is
+// searched for at most the timeout in milliseconds, 10 seconds by default. A
+// summary of the blunders and inaccuracies of both players is written to
+// stderr.
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let path = match args.first() {
+        Some(path) => path,
+        None => {
+            eprintln!("Usage: annotate <records> [timeout_ms] [game]");
+            std::process::exit(1);
+        }
+    };
+    let timeout = args.get(1).and_then(|ms| ms.parse().ok()).unwrap_or(10_000);
+    let timeout = std::time::Duration::from_millis(timeout);
+
+    let mut games = match record::load(path) {
+        Ok(games) => games,
+        Err(error) => {
+            eprintln!("Error: Could not read {}: {}", path, error);
+            std::process::exit(1);
+        }
+    };
+    if let Some(game) = args.get(2).and_then(|game| game.parse::<usize>().ok()) {
+        if game == 0 || game > games.len() {
+            eprintln!("Error: The file has {} games.", games.len());
+            std::process::exit(1);
+        }
+        games = vec![games.swap_remove(game - 1)];
+    }
+
+    let mut session = solver::Session::from_gb(1.0);
+    let mut rules = None;
+    for (index, game) in games.iter().enumerate() {
+        // The table only holds for the rules it was filled with.
+        if rules.is_some_and(|rules| rules != game.rules) {
+            session.clear();
+        }
+        rules = Some(game.rules);
+
+        let (annotated, judgements) = match annotate::annotate(&mut session, game, timeout) {
+            Ok(annotation) => annotation,
+            Err(error) => {
+                eprintln!("Error: Could not annotate game {}: {}", index + 1, error);
+                continue;
+            }
+        };
+        if index > 0 {
+            println!();
+        }
+        print!("{}", annotated);
+
+        // The blunders and inaccuracies of player1 and player2.
+        let mut blunders = [0, 0];
+        let mut inaccuracies = [0, 0];
+        let mut unsolved = 0;
+        for (index, judgement) in judgements.iter().enumerate() {
+            match judgement {
+                Some(annotate::Judgement::Blunder) => blunders[index % 2] += 1,
+                Some(annotate::Judgement::Inaccuracy) => inaccuracies[index % 2] += 1,
+                Some(annotate::Judgement::Best) => (),
+                None => unsolved += 1,
+            }
+        }
+        eprintln!(
+            "game {}: {} {} blunders {} inaccuracies, {} {} blunders {} inaccuracies, {} moves not solved",
+            index + 1,
+            game.players[0],
+            blunders[0],
+            inaccuracies[0],
+            game.players[1],
+            blunders[1],
+            inaccuracies[1],
+            unsolved
+        );
+    }
+}
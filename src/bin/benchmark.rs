@@ -1,6 +1,6 @@
-use katalon::{board, eval, solver};
+use katalon::{board, eval, solver, stats::search};
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Write};
 use tabled::{Table, Tabled};
 
 /// Test set data format
@@ -11,6 +11,9 @@ use tabled::{Table, Tabled};
 /// Table headers
 /// | solver | test set | mean time | max time | mean visited | max visited | visited / s |
 
+/// Usage: benchmark [--csv <file>]
+///   With --csv the stats of every search are also written to the file.
+
 struct Entry {
     pub board: board::Board,
     pub eval: eval::Eval,
@@ -56,7 +59,7 @@ fn load_file(filename: String) -> Vec<Entry> {
     entries
 }
 
-fn run_set(name: String, entries: Vec<Entry>) -> Result<Bench, ()> {
+fn run_set(name: String, entries: Vec<Entry>, csv: &mut Vec<String>) -> Result<Bench, ()> {
     let mut time = Vec::<std::time::Duration>::new();
     let mut visited = Vec::<usize>::new();
 
@@ -66,6 +69,7 @@ fn run_set(name: String, entries: Vec<Entry>) -> Result<Bench, ()> {
     for (index, entry) in entries.iter().enumerate() {
         let (result, stats) =
             solver::eval_with_stats(&entry.board, std::time::Duration::from_secs(10));
+        csv.push(format!("{},{},{}", name, index, stats.to_csv()));
 
         if let Ok(eval) = result {
             if eval != entry.eval {
@@ -105,6 +109,18 @@ fn run_set(name: String, entries: Vec<Entry>) -> Result<Bench, ()> {
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let csv_path = match args.iter().position(|arg| arg == "--csv") {
+        Some(index) => match args.get(index + 1) {
+            Some(path) => Some(path.clone()),
+            None => {
+                println!("Usage: benchmark [--csv <file>]");
+                return;
+            }
+        },
+        None => None,
+    };
+
     #[rustfmt::skip]
     let names = [
         "depth25",
@@ -115,13 +131,22 @@ fn main() {
     ];
 
     let mut benches = Vec::<Bench>::new();
+    let mut csv = vec![format!("test_set,index,{}", search::Stats::csv_header())];
     for name in names {
         let filename = format!("res/benchmark/{}.txt", name);
         let entries = load_file(filename.clone());
 
-        if let Ok(bench) = run_set(format!("{}", name), entries) {
+        if let Ok(bench) = run_set(format!("{}", name), entries, &mut csv) {
             benches.push(bench);
         }
     }
     println!("{}", Table::new(benches).to_string());
+
+    if let Some(path) = csv_path {
+        let mut file =
+            File::create(&path).unwrap_or_else(|_| panic!("Could not create file {}.", &path));
+        for line in csv {
+            writeln!(file, "{}", line).expect("Could not write the stats.");
+        }
+    }
 }
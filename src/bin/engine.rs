@@ -0,0 +1,119 @@
+use katalon::{board, engine, solver};
+use std::io::BufRead;
+
+// Usage: engine
+// Reads commands of the engine protocol from stdin and answers on stdout,
+// see the engine module for the protocol. The table is kept between the
+// searches, which are often of positions close to each other.
+
+/// A search running in the background, so stop commands can still be read.
+struct Search {
+    stop: solver::StopHandle,
+    thread: std::thread::JoinHandle<()>,
+}
+
+impl Search {
+    /// Start searching the board within the limits, answering with the first
+    /// move of the principal variation.
+    fn start(
+        session: std::sync::Arc<std::sync::Mutex<solver::Session>>,
+        board: board::Board,
+        limits: solver::SearchLimits,
+    ) -> Self {
+        let stop = solver::StopHandle::new();
+        let limits = limits.stop(stop.clone());
+
+        let thread = std::thread::spawn(move || {
+            // The session takes its table back when a search panics, so it
+            // stays usable for the next searches.
+            let mut session = session
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            let (result, stats) = session.pv_with_progress(
+                &board,
+                limits,
+                std::time::Duration::from_secs(1),
+                |progress| println!("{}", engine::info_progress(progress)),
+            );
+
+            match result {
+                Ok((value, pv)) if !pv.is_empty() => {
+                    println!("{}", engine::info_result(value, &pv, &stats));
+                    println!("bestmove {}", pv[0]);
+                }
+                // Always answer with a move, even if the search did not finish.
+                result => {
+                    if let Err(error) = result {
+                        println!("{}", engine::info_string(&error));
+                    }
+                    match board.ordered().next() {
+                        Some(mv) if board.isover().is_none() => {
+                            println!(
+                                "{}",
+                                engine::info_string(format!(
+                                    "The search gave no move, {} is the first move of the move ordering.",
+                                    mv
+                                ))
+                            );
+                            println!("bestmove {}", mv);
+                        }
+                        _ => println!("bestmove none"),
+                    }
+                }
+            }
+        });
+
+        Self { stop, thread }
+    }
+}
+
+/// Stop the search if it is still running and wait until it answered.
+fn finish(search: &mut Option<Search>) {
+    if let Some(search) = search.take() {
+        search.stop.stop();
+        search.thread.join().expect("The search thread panicked.");
+    }
+}
+
+fn main() {
+    let session = std::sync::Arc::new(std::sync::Mutex::new(solver::Session::from_gb(1.0)));
+    let mut board = board::Board::new();
+    let mut search: Option<Search> = None;
+
+    for line in std::io::stdin().lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match engine::Command::parse(&line) {
+            Ok(engine::Command::Katalon) => {
+                println!("id name katalon {}", env!("CARGO_PKG_VERSION"));
+                println!("katalonok");
+            }
+            Ok(engine::Command::IsReady) => println!("readyok"),
+            Ok(engine::Command::NewGame) => {
+                finish(&mut search);
+                board = board::Board::new();
+            }
+            Ok(engine::Command::Position(position)) => {
+                finish(&mut search);
+                board = position;
+            }
+            Ok(engine::Command::Go(limits)) => {
+                finish(&mut search);
+                search = Some(Search::start(session.clone(), board.clone(), limits));
+            }
+            Ok(engine::Command::Stop) => {
+                finish(&mut search);
+            }
+            Ok(engine::Command::Quit) => break,
+            Err(error) => println!("{}", engine::info_string(error)),
+        }
+    }
+
+    finish(&mut search);
+}
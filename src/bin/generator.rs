@@ -3,37 +3,27 @@ use katalon::{board, eval, player::Player, random, solver};
 /// Generate a board and its notation with movecount equal to given depth.
 fn generate(depth: usize) -> (board::Board, String) {
     let player = random::Random;
-    let mut board;
-    let mut notation;
+    let mut position;
 
     loop {
         let mut depth_reached = true;
 
-        board = board::Board::new();
-        notation = String::new();
+        position = board::Position::new();
 
         for _ in 0..depth {
-            // Get the move from the player.
-            let (square, cell) = player.play(&board);
-
-            // Update the notation.
-            if board.isfirst() {
-                notation.push_str(&square.to_string());
-            }
-            notation.push_str(&cell.to_string());
-
-            // Make the move.
-            board.play(square, cell);
+            // Get the move from the player and make it.
+            let mv = player.play(position.board());
+            position.play(mv).expect("The player made an illegal move.");
 
             // Early exit if the game is over.
-            if board.isover() != None {
+            if position.board().isover() != None {
                 depth_reached = false;
                 break;
             }
         }
 
         if depth_reached {
-            return (board, notation);
+            return (position.board().clone(), position.notation());
         }
     }
 }
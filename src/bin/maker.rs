@@ -1,22 +1,53 @@
 use cmd::Cmd;
-use katalon::{board, input, player::Player, random, solver};
+use katalon::{board, input, player::Player, random, record, solver, stats::search};
 use rand::Rng;
+use std::io::Write;
 
 pub struct State {
-    board: board::Board,
-    notation: String,
+    position: board::Position,
 }
 
 impl std::fmt::Display for State {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if self.board.isfirst() {
-            write!(f, "{}", self.board)
+        if self.position.board().isfirst() {
+            write!(f, "{}", self.position)
         } else {
-            write!(f, "{}= {}\n", self.board, self.notation)
+            write!(f, "{}= {}\n", self.position, self.position.notation())
         }
     }
 }
 
+/// Search the best moves while showing the progress on a single line.
+fn search_bestmoves(
+    board: &board::Board,
+    timeout: u64,
+) -> (
+    katalon::error::Result<(katalon::eval::Eval, Vec<board::Move>)>,
+    search::Stats,
+) {
+    let mut reported = false;
+    let result = solver::bestmoves_with_progress(
+        board,
+        std::time::Duration::from_secs(timeout),
+        std::time::Duration::from_secs(1),
+        |progress| {
+            reported = true;
+            print!("\rthinking {}", progress);
+            std::io::stdout().flush().ok();
+        },
+    );
+    if reported {
+        println!();
+    }
+    result
+}
+
+/// Format the moves as a list, e.g. [23, 24].
+fn format_moves(moves: &[board::Move]) -> String {
+    let moves: Vec<String> = moves.iter().map(|mv| mv.to_string()).collect();
+    format!("[{}]", moves.join(", "))
+}
+
 mod cmd {
     use super::*;
 
@@ -24,28 +55,27 @@ mod cmd {
     pub type Cmd = fn(&mut State, &[&str]) -> bool;
 
     pub fn play(state: &mut State, args: &[&str]) -> bool {
-        if state.board.isover() != None {
-            println!("Warn: the game already finished.");
+        if state.position.board().isover() != None {
+            println!("Warn: The game already finished.");
             return false;
         }
 
-        let extracted = input::extract(&state.board, &args[0]);
+        let extracted = input::extract(state.position.board(), &args[0]);
         if let Err(e) = extracted {
-            println!("{}", e);
+            println!("Error: {}", e);
             return false;
         }
-        let (square, cell) = extracted.unwrap();
+        let mv = extracted.unwrap();
 
         // Update the board and notation
-        if state.board.isfirst() {
-            state.notation.push_str(&square.to_string());
-        }
-        state.notation.push_str(&cell.to_string());
-        state.board.play(square, cell);
+        state
+            .position
+            .play(mv)
+            .expect("The move was already checked.");
 
         print!("{}", state);
 
-        match state.board.isover() {
+        match state.position.board().isover() {
             Some(board::Result::Draw) => println!("It's a draw!"),
             Some(result) => println!("Player {} won!", result.player().unwrap()),
             None => (),
@@ -54,24 +84,22 @@ mod cmd {
     }
 
     pub fn undo(state: &mut State, _args: &[&str]) -> bool {
-        match state.board.movecount() {
-            0 => return false,
-            1 => {
-                state.notation.clear();
-                state.board = board::Board::new();
-            }
-            _ => {
-                state.notation.pop();
-                state.board = board::Board::load(&state.notation).unwrap();
-            }
+        if state.position.undo().is_some() {
+            print!("{}", state);
+        }
+        false
+    }
+
+    pub fn redo(state: &mut State, _args: &[&str]) -> bool {
+        if state.position.redo().is_some() {
+            print!("{}", state);
         }
-        print!("{}", state);
         false
     }
 
     pub fn eval(state: &mut State, args: &[&str]) -> bool {
-        if state.board.isover() != None {
-            println!("Warn: the game already finished.");
+        if state.position.board().isover() != None {
+            println!("Warn: The game already finished.");
             return false;
         }
 
@@ -80,22 +108,21 @@ mod cmd {
             if let Ok(time) = args[1].parse::<u64>() {
                 timeout = time;
             } else {
-                println!("Warn: invalid timeout ignored");
+                println!("Warn: Invalid timeout ignored");
             }
         }
 
-        let (result, stats) =
-            solver::bestmoves_with_stats(&state.board, std::time::Duration::from_secs(timeout));
+        let (result, stats) = search_bestmoves(state.position.board(), timeout);
 
         if result.is_err() {
             println!("timeout after {}s", timeout);
         } else {
             let (value, bestmoves) = result.unwrap();
             println!(
-                "evaluation: {} ({}ms)\nmoves: {:?}",
+                "evaluation: {} ({}ms)\nmoves: {}",
                 value,
                 stats.time.as_millis(),
-                bestmoves
+                format_moves(&bestmoves)
             );
         }
         print!("\n{}", stats);
@@ -104,8 +131,8 @@ mod cmd {
     }
 
     pub fn best(state: &mut State, args: &[&str]) -> bool {
-        if state.board.isover() != None {
-            println!("Warn: the game already finished.");
+        if state.position.board().isover() != None {
+            println!("Warn: The game already finished.");
             return false;
         }
 
@@ -115,12 +142,11 @@ mod cmd {
             if let Ok(time) = args[1].parse::<u64>() {
                 timeout = time;
             } else {
-                println!("Warn: invalid timeout ignored");
+                println!("Warn: Invalid timeout ignored");
             }
         }
 
-        let (result, stats) =
-            solver::bestmoves_with_stats(&state.board, std::time::Duration::from_secs(timeout));
+        let (result, stats) = search_bestmoves(state.position.board(), timeout);
 
         if result.is_err() {
             println!("timeout after {}s", timeout);
@@ -132,14 +158,14 @@ mod cmd {
         let bestmove = bestmoves[rng.gen_range(0..bestmoves.len()) as usize];
 
         println!(
-            "evaluation: {} ({}ms)\n{:?} -> {:?}",
+            "evaluation: {} ({}ms)\n{} -> {}",
             value,
             stats.time.as_millis(),
-            bestmoves,
+            format_moves(&bestmoves),
             bestmove
         );
 
-        let builder = format!("{}{}", bestmove.0, bestmove.1);
+        let builder = bestmove.to_string();
         let args = vec![builder.as_str()];
 
         cmd::play(state, &args[..]);
@@ -148,13 +174,12 @@ mod cmd {
     }
 
     pub fn random(state: &mut State, _args: &[&str]) -> bool {
-        if state.board.isover() != None {
-            println!("Warn: the game already finished.");
+        if state.position.board().isover() != None {
+            println!("Warn: The game already finished.");
             return false;
         }
 
-        let (square, cell) = random::Random.play(&state.board);
-        let builder = format!("{}{}", square, cell);
+        let builder = random::Random.play(state.position.board()).to_string();
 
         cmd::play(state, &[&builder.as_str()]);
 
@@ -162,8 +187,7 @@ mod cmd {
     }
 
     pub fn new(state: &mut State, _args: &[&str]) -> bool {
-        state.board = board::Board::new();
-        state.notation.clear();
+        state.position = board::Position::new();
 
         print!("{}", state);
         false
@@ -171,15 +195,14 @@ mod cmd {
 
     pub fn load(state: &mut State, args: &[&str]) -> bool {
         if args.len() < 2 {
-            println!("Error: please provide a game to load.");
+            println!("Error: Please provide a game to load.");
             return false;
         }
 
-        let board = board::Board::load(args[1]);
-        match board {
-            Ok(board) => {
-                state.board = board;
-                state.notation = String::from(args[1]);
+        let position = board::Position::load(args[1]);
+        match position {
+            Ok(position) => {
+                state.position = position;
                 print!("{}", state);
             }
             Err(e) => {
@@ -189,18 +212,81 @@ mod cmd {
         false
     }
 
+    pub fn write(state: &mut State, args: &[&str]) -> bool {
+        if args.len() < 2 {
+            println!("Error: Please provide a file to write to.");
+            return false;
+        }
+
+        let mut game =
+            record::Record::from_moves(board::Rules::default(), state.position.history())
+                .expect("The moves were made on the board.");
+        game.date = Some(record::today());
+        match record::append(args[1], &game) {
+            Ok(()) => println!("Wrote the game to {}.", args[1]),
+            Err(e) => println!("{}", e),
+        }
+        false
+    }
+
+    pub fn open(state: &mut State, args: &[&str]) -> bool {
+        if args.len() < 2 {
+            println!("Error: Please provide a file to open.");
+            return false;
+        }
+
+        let games = match record::load(args[1]) {
+            Ok(games) => games,
+            Err(e) => {
+                println!("{}", e);
+                return false;
+            }
+        };
+        // The games are numbered from 1, without a number the last is opened.
+        let index = match args.get(2).map(|index| index.parse::<usize>()) {
+            None => games.len(),
+            Some(Ok(index)) => index,
+            Some(Err(_)) => {
+                println!("Error: The game should be a number.");
+                return false;
+            }
+        };
+        let game = match index.checked_sub(1).and_then(|index| games.get(index)) {
+            Some(game) => game,
+            None => {
+                println!("Error: The file has {} games.", games.len());
+                return false;
+            }
+        };
+        if game.rules != board::Rules::default() {
+            println!("Error: The maker only supports the default rules.");
+            return false;
+        }
+
+        let mut position = board::Position::new();
+        for annotated in &game.moves {
+            if let Err(e) = position.play(annotated.mv) {
+                println!("{}", e);
+                return false;
+            }
+        }
+        state.position = position;
+        print!("{}", state);
+        false
+    }
+
     pub fn count(state: &mut State, _args: &[&str]) -> bool {
-        println!("movecount: {}", state.board.movecount());
+        println!("movecount: {}", state.position.board().movecount());
         false
     }
 
     pub fn take(state: &mut State, _args: &[&str]) -> bool {
-        println!("takestreak: {}", state.board.takestreak());
+        println!("takestreak: {}", state.position.board().takestreak());
         false
     }
 
     pub fn square(state: &mut State, _args: &[&str]) -> bool {
-        match state.board.square() {
+        match state.position.board().square() {
             Some(square) => println!("square: {}", square),
             None => println!("square: none"),
         }
@@ -220,11 +306,14 @@ mod cmd {
         println!(concat!(
             "[0-4]<0-4>: make move\n",
             "u undo: undo last move\n",
+            "y redo: redo last undone move\n",
             "e eval [timeout]: evaluate state\n",
             "b best [timeout]: make best move\n",
             "r random: make random move\n",
             "n new: new game\n",
             "l load: load game\n",
+            "w write <file>: append game to record file\n",
+            "o open <file> [game]: open game from record file\n",
             "c count: print movecount\n",
             "t take: print takestreak\n",
             "s square: print square\n",
@@ -238,7 +327,7 @@ mod cmd {
 
 fn command(state: &mut State, prevcmd: &mut Option<Cmd>) -> bool {
     // Get the user command input
-    let line = input::request(format!("{} > ", state.board.onturn()));
+    let line = input::request(format!("{} > ", state.position.board().onturn()));
     let cmd: Option<Cmd>;
 
     // Process the command
@@ -247,11 +336,14 @@ fn command(state: &mut State, prevcmd: &mut Option<Cmd>) -> bool {
         cmd = match args[0] {
             input if input::move_regex().is_match(input) => Some(cmd::play),
             "u" | "undo" => Some(cmd::undo),
+            "y" | "redo" => Some(cmd::redo),
             "e" | "eval" => Some(cmd::eval),
             "b" | "best" => Some(cmd::best),
             "r" | "random" => Some(cmd::random),
             "n" | "new" => Some(cmd::new),
             "l" | "load" => Some(cmd::load),
+            "w" | "write" => Some(cmd::write),
+            "o" | "open" => Some(cmd::open),
             "c" | "count" => Some(cmd::count),
             "t" | "take" => Some(cmd::take),
             "s" | "square" => Some(cmd::square),
@@ -259,7 +351,7 @@ fn command(state: &mut State, prevcmd: &mut Option<Cmd>) -> bool {
             "q" | "quit" => Some(cmd::quit),
             "h" | "help" => Some(cmd::help),
             _ => {
-                println!("Error: invalid command, see 'help'.");
+                println!("Error: Invalid command, see 'help'.");
                 None
             }
         };
@@ -267,6 +359,7 @@ fn command(state: &mut State, prevcmd: &mut Option<Cmd>) -> bool {
         // Update the prevcmd
         match args[0] {
             "u" | "undo" => *prevcmd = Some(cmd::undo),
+            "y" | "redo" => *prevcmd = Some(cmd::redo),
             "b" | "best" => *prevcmd = Some(cmd::best),
             "r" | "random" => *prevcmd = Some(cmd::random),
             _ => *prevcmd = None,
@@ -286,11 +379,10 @@ fn command(state: &mut State, prevcmd: &mut Option<Cmd>) -> bool {
 fn main() {
     let mut prevcmd: Option<Cmd> = None;
     let mut state = State {
-        board: board::Board::new(),
-        notation: String::new(),
+        position: board::Position::new(),
     };
 
-    print!("{}", state.board);
+    print!("{}", state);
     loop {
         if command(&mut state, &mut prevcmd) {
             break;
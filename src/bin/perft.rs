@@ -0,0 +1,59 @@
+use katalon::{board, perft};
+use tabled::{Table, Tabled};
+
+/// Usage: perft <depth> [moves] [--parallel]
+///   e.g. perft 8 --parallel
+///        perft 6 221400203101122
+
+#[derive(Debug, Tabled)]
+struct Row {
+    pub depth: usize,
+    pub total: usize,
+    pub unique: usize,
+    pub unique_per_total: String,
+    pub player1: usize,
+    pub player2: usize,
+    pub draw: usize,
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let parallel = args.iter().any(|arg| arg == "--parallel");
+    let args: Vec<&String> = args.iter().filter(|arg| *arg != "--parallel").collect();
+
+    let depth = match args.first().map(|depth| depth.parse::<usize>()) {
+        Some(Ok(depth)) => depth,
+        _ => {
+            println!("Usage: perft <depth> [moves] [--parallel]");
+            return;
+        }
+    };
+
+    let board = match board::Board::load(args.get(1).map_or("", |moves| moves.as_str())) {
+        Ok(board) => board,
+        Err(error) => {
+            println!("{}", error);
+            return;
+        }
+    };
+
+    let now = std::time::Instant::now();
+    let counts = perft::perft(&board, depth, parallel);
+
+    let rows: Vec<Row> = counts
+        .iter()
+        .enumerate()
+        .map(|(depth, counts)| Row {
+            depth,
+            total: counts.total,
+            unique: counts.unique,
+            unique_per_total: format!("{:.4}", counts.unique as f64 / counts.total as f64),
+            player1: counts.player1,
+            player2: counts.player2,
+            draw: counts.draw,
+        })
+        .collect();
+
+    println!("{}", Table::new(rows));
+    println!("finished in {} ms", now.elapsed().as_millis());
+}
@@ -0,0 +1,33 @@
+use katalon::{server, solver};
+
+// Usage: serve [port] [timeout_ms]
+//   e.g. serve 8080 10000
+//        curl 'localhost:8080/bestmoves?moves=0020'
+// Serves the analysis over HTTP on localhost, see the server module for the
+// requests. The timeout is the longest time a single search may take.
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let port: u16 = args
+        .first()
+        .and_then(|port| port.parse().ok())
+        .unwrap_or(8080);
+    let timeout = args.get(1).and_then(|ms| ms.parse().ok()).unwrap_or(10_000);
+
+    // The table lives as long as the server, so it is shared by all searches.
+    let session = solver::Session::from_gb(1.0);
+    let server = match server::Server::bind(
+        ("127.0.0.1", port),
+        session,
+        std::time::Duration::from_millis(timeout),
+    ) {
+        Ok(server) => server,
+        Err(error) => {
+            eprintln!("Error: Could not listen on port {}: {}", port, error);
+            std::process::exit(1);
+        }
+    };
+
+    println!("Listening on http://{}", server.local_addr().unwrap());
+    server.run();
+}
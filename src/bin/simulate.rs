@@ -1,8 +1,86 @@
-use katalon::{board, game};
+use katalon::{board, external, game};
 #[allow(unused_imports)]
 use katalon::{human, random, solver};
 
+// Usage: simulate [<engine1> <engine2> [games] [movetime] [records]]
+//   e.g. simulate
+//        simulate ./engine-old ./engine-new 20 1000 games.txt
+// Without engines random plays against the solver forever. With engines they
+// play the given number of games against each other, switching sides every
+// game, thinking the movetime in milliseconds about every move. The games are
+// appended to the records file if one is given.
+
+/// Play the engines against each other and print the score.
+fn tournament(
+    engines: [&str; 2],
+    games: usize,
+    movetime: std::time::Duration,
+    records: Option<&str>,
+) {
+    // The wins of the engines and the number of draws.
+    let mut score = [0, 0, 0];
+
+    for round in 0..games {
+        let spawn = |engine: &str| match external::External::spawn(engine, &[], movetime) {
+            Ok(player) => Box::new(player),
+            Err(error) => {
+                println!("Error: Could not start {}: {}", engine, error);
+                std::process::exit(1);
+            }
+        };
+
+        // Switch sides every game, first is the index of the engine playing first.
+        let first = round % 2;
+        let mut game = game::Game::new(spawn(engines[first]), spawn(engines[1 - first]), false);
+
+        let result = game.run();
+        let winner = result.player().map(|player| (player as usize + first) % 2);
+        match winner {
+            Some(winner) => score[winner] += 1,
+            None => score[2] += 1,
+        }
+
+        print!("game {}: ", round + 1);
+        match winner {
+            Some(winner) => print!("{} won", engines[winner]),
+            None => print!("draw"),
+        }
+        match game.forfeit() {
+            Some((_, error)) => println!(" by forfeit, {}", error),
+            None => println!(),
+        }
+
+        if let Some(records) = records {
+            if let Err(error) = game.save(records) {
+                println!("Error: Could not save the game to {}: {}", records, error);
+            }
+        }
+    }
+
+    println!(
+        "{} {} - {} {}, {} draws",
+        engines[0], score[0], score[1], engines[1], score[2]
+    );
+}
+
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if args.len() >= 2 {
+        let games = args
+            .get(2)
+            .and_then(|games| games.parse().ok())
+            .unwrap_or(10);
+        let movetime = args.get(3).and_then(|ms| ms.parse().ok()).unwrap_or(1000);
+        tournament(
+            [&args[0], &args[1]],
+            games,
+            std::time::Duration::from_millis(movetime),
+            args.get(4).map(String::as_str),
+        );
+        return;
+    }
+
     loop {
         let player1 = Box::new(random::Random);
         let player2 = Box::new(solver::Solver);
@@ -0,0 +1,40 @@
+use katalon::{board, verify};
+
+// Usage: verify <depth> [moves]
+//   e.g. verify 10
+//        verify 8 221400203101122
+// Checks that all the boards with the same key are the same position.
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let depth = match args.first().map(|depth| depth.parse::<usize>()) {
+        Some(Ok(depth)) => depth,
+        _ => {
+            println!("Usage: verify <depth> [moves]");
+            return;
+        }
+    };
+
+    let board = match board::Board::load(args.get(1).map_or("", |moves| moves.as_str())) {
+        Ok(board) => board,
+        Err(error) => {
+            println!("{}", error);
+            return;
+        }
+    };
+
+    let now = std::time::Instant::now();
+    match verify::verify(&board, depth) {
+        Ok(summary) => {
+            println!("visited  = {}", summary.visited);
+            println!("keys     = {}", summary.keys);
+            println!("compared = {}", summary.compared);
+            println!("no collisions found in {} ms", now.elapsed().as_millis());
+        }
+        Err(collision) => {
+            println!("{}", collision);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,293 @@
+use super::{Board, Rules};
+use crate::{error, player};
+
+/// The position notation is a single line consisting of six fields:
+///   cells onturn stones lastmove takestreak movecount
+/// e.g. ".X.../.O.../...../...../..... X 11,11 11 0 2"
+///
+/// cells: the five squares separated by '/', each square lists the cells 0 to 4
+///        as 'X' (player1), 'O' (player2) or '.' (empty)
+/// onturn: the player onturn, 'X' or 'O'
+/// stones: the number of stones left for player1 and player2, e.g. "11,12"
+/// lastmove: the last move as square and cell, e.g. "34", or '-' if there is none
+/// takestreak: how many turns in a row stones have been taken
+/// movecount: the number of moves made so far
+impl Board {
+    /// Return the position notation of this board.
+    pub fn to_fen(&self) -> String {
+        let symbol = |index: u8| -> String {
+            if self.mask & 1 << index == 0 {
+                ".".to_string()
+            } else if self.state & 1 << index == 0 {
+                self.onturn.other().to_string()
+            } else {
+                self.onturn.to_string()
+            }
+        };
+
+        let cells: Vec<String> = (0..5)
+            .map(|square| (0..5).map(|cell| symbol(square * 5 + cell)).collect())
+            .collect();
+
+        let lastmove = match self.lastmove {
+            Some((square, cell)) => format!("{}{}", square, cell),
+            None => String::from("-"),
+        };
+
+        format!(
+            "{} {} {},{} {} {} {}",
+            cells.join("/"),
+            self.onturn,
+            self.stones[0],
+            self.stones[1],
+            lastmove,
+            self.takestreak,
+            self.movecount
+        )
+    }
+
+    /// Create a board from the given position notation.
+    pub fn from_fen(fen: &str) -> error::Result<Self> {
+        Self::from_fen_with_rules(fen, Rules::default())
+    }
+
+    /// Create a board on which is played with the given rules from the given
+    /// position notation.
+    pub fn from_fen_with_rules(fen: &str, rules: Rules) -> error::Result<Self> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() != 6 {
+            return Err(error::Error::Notation(format!(
+                "The position should have 6 fields, not {}.",
+                fields.len()
+            )));
+        }
+
+        let mut board = Self::with_rules(rules);
+
+        // Load the player onturn, this is needed to interpret the cells.
+        board.onturn = match fields[1] {
+            "X" => player::Players::Player1,
+            "O" => player::Players::Player2,
+            other => {
+                return Err(error::Error::Notation(format!(
+                    "The player onturn should be X or O, not {}.",
+                    other
+                )))
+            }
+        };
+
+        // Load the cells, all squares should have exactly five cells.
+        let squares: Vec<&str> = fields[0].split('/').collect();
+        if squares.len() != 5 || squares.iter().any(|square| square.chars().count() != 5) {
+            return Err(error::Error::Notation(String::from(
+                "The cells should be five squares of five cells separated by '/'.",
+            )));
+        }
+
+        let onturn = board.onturn.to_string();
+        for (index, c) in squares.concat().chars().enumerate() {
+            let bit = 1 << index;
+            match c {
+                '.' => (),
+                'X' | 'O' => {
+                    board.mask |= bit;
+                    if c.to_string() == onturn {
+                        board.state |= bit;
+                    }
+                }
+                other => {
+                    return Err(error::Error::Notation(format!(
+                        "Invalid cell symbol {}.",
+                        other
+                    )))
+                }
+            }
+        }
+
+        // Load the number of stones left for both players.
+        let stones: Vec<&str> = fields[2].split(',').collect();
+        if stones.len() != 2 {
+            return Err(error::Error::Notation(String::from(
+                "The stones should be given as two numbers, e.g. 11,12.",
+            )));
+        }
+        for (index, stones) in stones.iter().enumerate() {
+            match stones.parse::<u8>() {
+                Ok(n) if n <= rules.stones() => board.stones[index] = n,
+                _ => {
+                    return Err(error::Error::Notation(format!(
+                        "The number of stones should be between 0 and {}.",
+                        rules.stones()
+                    )))
+                }
+            }
+        }
+
+        // Load the lastmove.
+        board.lastmove = match fields[3] {
+            "-" => None,
+            lastmove => {
+                let digits: Vec<u8> = lastmove
+                    .chars()
+                    .filter(|c| ('0'..='4').contains(c))
+                    .map(|c| c as u8 - b'0')
+                    .collect();
+                if digits.len() != 2 || lastmove.len() != 2 {
+                    return Err(error::Error::Notation(String::from(
+                        "The lastmove should be a square and cell, e.g. 34, or -.",
+                    )));
+                }
+                Some((digits[0], digits[1]))
+            }
+        };
+
+        // Load the takestreak.
+        board.takestreak = match fields[4].parse::<u8>() {
+            Ok(n) if n <= rules.takestreak_limit() => n,
+            _ => {
+                return Err(error::Error::Notation(format!(
+                    "The takestreak should be between 0 and {}.",
+                    rules.takestreak_limit()
+                )))
+            }
+        };
+
+        // Load the movecount.
+        board.movecount = match fields[5].parse::<i16>() {
+            Ok(n) if (0..=rules.movecount_limit()).contains(&n) => n,
+            _ => {
+                return Err(error::Error::Notation(format!(
+                    "The movecount should be between 0 and {}.",
+                    rules.movecount_limit()
+                )))
+            }
+        };
+
+        // Only the first move has no lastmove.
+        if board.movecount == 0 && (board.lastmove.is_some() || board.mask != 0) {
+            return Err(error::Error::Notation(String::from(
+                "A position without moves should be empty.",
+            )));
+        } else if board.movecount > 0 && board.lastmove.is_none() {
+            return Err(error::Error::Notation(String::from(
+                "A position with moves should have a lastmove.",
+            )));
+        }
+
+        // Check whether the position as a whole is consistent.
+        board.validate()?;
+
+        board.rehash();
+        Ok(board)
+    }
+}
+
+/// A board is serialized as its position notation, the rules are not part of
+/// it so boards are read with the default rules.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Board {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_fen())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Board {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let fen = String::deserialize(deserializer)?;
+        Board::from_fen(&fen).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test the notation of the empty board.
+    #[test]
+    fn fen_empty() {
+        let fen = "...../...../...../...../..... X 12,12 - 0 0";
+        assert_eq!(Board::new().to_fen(), fen);
+        assert_eq!(Board::from_fen(fen).unwrap().key(), 0);
+    }
+
+    /// Test the notation of a board with moves and takes.
+    #[test]
+    fn fen_load() {
+        let board = Board::load("01234321003040223").unwrap();
+        let fen = "XXOOO/O.OX./OXXOX/XOO.O/X..X. X 5,4 23 1 16";
+        assert_eq!(board.to_fen(), fen);
+
+        let loaded = Board::from_fen(fen).unwrap();
+        assert_eq!(loaded.state, board.state);
+        assert_eq!(loaded.mask, board.mask);
+        assert_eq!(loaded.onturn, board.onturn);
+        assert_eq!(loaded.stones, board.stones);
+        assert_eq!(loaded.lastmove, board.lastmove);
+        assert_eq!(loaded.takestreak, board.takestreak);
+        assert_eq!(loaded.movecount, board.movecount);
+    }
+
+    /// Test converting random boards back and forth.
+    #[test]
+    fn fen_many() {
+        for _ in 0..1000 {
+            let board = Board::random();
+            let loaded = Board::from_fen(&board.to_fen()).unwrap();
+
+            assert_eq!(loaded.key(), board.key());
+            assert_eq!(loaded.stones, board.stones);
+            assert_eq!(loaded.lastmove, board.lastmove);
+            assert_eq!(loaded.movecount, board.movecount);
+            assert_eq!(loaded.isover(), board.isover());
+        }
+    }
+
+    /// Test some basic error handling.
+    #[test]
+    fn fen_errors() {
+        // wrong number of fields
+        assert!(Board::from_fen("...../...../...../...../..... X 12,12 - 0").is_err());
+        // wrong number of cells
+        assert!(Board::from_fen("..../...../...../...../..... X 12,12 - 0 0").is_err());
+        // invalid symbol
+        assert!(Board::from_fen("..a../...../...../...../..... X 12,12 - 0 0").is_err());
+        // invalid player
+        assert!(Board::from_fen("...../...../...../...../..... Y 12,12 - 0 0").is_err());
+        // double cells differ
+        assert!(matches!(
+            Board::from_fen("....X/...../...../...../..... O 11,12 04 0 1"),
+            Err(error::Error::Position(crate::board::Invalid::Double(..)))
+        ));
+        // too many stones
+        assert!(Board::from_fen("...../...../...../...../..... X 13,12 - 0 0").is_err());
+        // invalid lastmove
+        assert!(Board::from_fen("....X/...../X..../...../..... O 11,12 05 0 1").is_err());
+        // takestreak too large
+        assert!(Board::from_fen("....X/...../X..../...../..... O 11,12 04 99 1").is_err());
+        // lastmove missing
+        assert!(Board::from_fen("....X/...../X..../...../..... O 11,12 - 0 1").is_err());
+
+        assert!(Board::from_fen("....X/...../X..../...../..... O 11,12 04 0 1").is_ok());
+    }
+
+    /// Test whether boards are serialized and read again.
+    #[cfg(feature = "serde")]
+    #[test]
+    fn fen_serde() {
+        let board = Board::load("01234321003040223").unwrap();
+        let json = serde_json::to_string(&board).unwrap();
+        assert_eq!(json, "\"XXOOO/O.OX./OXXOX/XOO.O/X..X. X 5,4 23 1 16\"");
+        assert_eq!(serde_json::from_str::<Board>(&json).unwrap(), board);
+
+        for _ in 0..100 {
+            let board = Board::random();
+            let json = serde_json::to_string(&board).unwrap();
+            assert_eq!(serde_json::from_str::<Board>(&json).unwrap(), board);
+        }
+        assert!(
+            serde_json::from_str::<Board>("\"..a../...../...../...../..... X 12,12 - 0 0\"")
+                .is_err()
+        );
+    }
+}
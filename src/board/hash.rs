@@ -0,0 +1,198 @@
+use super::{Board, Symmetry};
+use crate::player;
+
+/// Generate a pseudo random number from the given seed with splitmix64, such
+/// that the hashes are the same on every run.
+const fn splitmix(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// The random numbers for a stone of a player on a cell for all the symmetries.
+/// ZOBRIST_CELLS[symmetry][index][player] is the number of the cell to which
+/// the index is mapped by the symmetry, such that the symmetrical hashes can be
+/// updated together with the normal hash.
+const ZOBRIST_CELLS: [[[u64; 2]; 25]; 8] = {
+    let mut cells = [[[0; 2]; 25]; 8];
+    let mut symmetry = 0;
+    while symmetry < 8 {
+        let mut index = 0;
+        while index < 25 {
+            let mapped = match symmetry {
+                0 => index,
+                _ => Board::SYMMETRIES[symmetry - 1][index],
+            };
+            cells[symmetry][index][0] = splitmix(2 * mapped as u64);
+            cells[symmetry][index][1] = splitmix(2 * mapped as u64 + 1);
+            index += 1;
+        }
+        symmetry += 1;
+    }
+    cells
+};
+
+/// The random number added when player2 is onturn.
+const ZOBRIST_ONTURN: u64 = splitmix(50);
+
+/// The random numbers for the takestreak, it takes 7 bits in the key.
+const ZOBRIST_TAKESTREAK: [u64; 128] = {
+    let mut takestreak = [0; 128];
+    let mut index = 0;
+    while index < 128 {
+        takestreak[index] = splitmix(51 + index as u64);
+        index += 1;
+    }
+    takestreak
+};
+
+/// The random numbers for the cell and the square of the lastmove.
+const ZOBRIST_LASTMOVE: [[u64; 5]; 2] = {
+    let mut lastmove = [[0; 5]; 2];
+    let mut index = 0;
+    while index < 5 {
+        lastmove[0][index] = splitmix(179 + index as u64);
+        lastmove[1][index] = splitmix(184 + index as u64);
+        index += 1;
+    }
+    lastmove
+};
+
+impl Board {
+    /// Add or remove a stone of the given player at the given index in the
+    /// hashes of the cells of all the symmetries.
+    pub(super) fn toggle_hashes(&mut self, index: u8, player: player::Players) {
+        for (hash, cells) in self.hashes.iter_mut().zip(ZOBRIST_CELLS.iter()) {
+            *hash ^= cells[index as usize][player as usize];
+        }
+    }
+
+    /// Compute the hashes of the cells from scratch. This is needed whenever
+    /// the state or mask is set directly instead of through play().
+    pub(super) fn rehash(&mut self) {
+        self.hashes = [0; 8];
+        for index in 0..25 {
+            let bit = 1 << index;
+            if self.mask & bit == 0 {
+                continue;
+            }
+            match self.state & bit {
+                0 => self.toggle_hashes(index, self.onturn.other()),
+                _ => self.toggle_hashes(index, self.onturn),
+            }
+        }
+    }
+
+    /// Return the hash of everything in the key except the cells, for the
+    /// board transformed by the given symmetry.
+    fn hash_rest(&self, symmetry: Symmetry) -> u64 {
+        let mut hash = ZOBRIST_TAKESTREAK[self.takestreak as usize];
+        if self.onturn == player::Players::Player2 {
+            hash ^= ZOBRIST_ONTURN;
+        }
+
+        // The square of the lastmove is only part of the key if the square we
+        // need to move into next is full, which is the same for all symmetries.
+        if let Some((square, cell)) = self.lastmove {
+            let (square, cell) = symmetry.map(square, cell);
+            hash ^= ZOBRIST_LASTMOVE[0][cell as usize];
+            if self.lastmove_square() {
+                hash ^= ZOBRIST_LASTMOVE[1][square as usize];
+            }
+        }
+        hash
+    }
+
+    /// Return a hash of this state of the board. Boards with the same key have
+    /// the same hash, use key() if a collision would matter.
+    pub fn hash(&self) -> u64 {
+        self.hashes[0] ^ self.hash_rest(Symmetry::Identity)
+    }
+
+    /// Return the hashes of all the symmetrical boards, in the order of
+    /// Symmetry::ALL. The hash at index i is the hash of transform(ALL[i]).
+    pub fn hashes(&self) -> [u64; 8] {
+        let mut hashes = self.hashes;
+        for (hash, symmetry) in hashes.iter_mut().zip(Symmetry::ALL) {
+            *hash ^= self.hash_rest(symmetry);
+        }
+        hashes
+    }
+
+    /// Return the same hash for all the boards in this equivalence class.
+    pub fn canonical_hash(&self) -> u64 {
+        *self.hashes().iter().min().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{player::Player, random};
+
+    /// Test whether the hashes updated by play and unplay match the hashes
+    /// computed from scratch.
+    #[test]
+    fn hash_incremental() {
+        for _ in 0..100 {
+            let mut board = Board::new();
+            let mut history = vec![];
+
+            while board.isover().is_none() {
+                let mv = random::Random.play(&board);
+                let before = board.clone();
+                history.push((before, board.play(mv.square, mv.cell)));
+
+                let mut rehashed = board.clone();
+                rehashed.rehash();
+                assert_eq!(board.hashes, rehashed.hashes);
+            }
+
+            while let Some((before, undo)) = history.pop() {
+                board.unplay(undo);
+                assert_eq!(board.hashes(), before.hashes());
+            }
+            assert_eq!(board.hash(), Board::new().hash());
+        }
+    }
+
+    /// Test whether the symmetrical hashes are the hashes of the transformed boards.
+    #[test]
+    fn hash_symmetries() {
+        for _ in 0..1000 {
+            let board = Board::random();
+            let hashes = board.hashes();
+
+            for (index, symmetry) in Symmetry::ALL.iter().enumerate() {
+                let transformed = board.transform(*symmetry);
+                assert_eq!(hashes[index], transformed.hash());
+                assert_eq!(board.canonical_hash(), transformed.canonical_hash());
+            }
+        }
+    }
+
+    /// Test whether boards have the same hash if and only if they have the same key.
+    #[test]
+    fn hash_key() {
+        let mut hashes = std::collections::HashMap::new();
+        let mut keys = std::collections::HashMap::new();
+
+        for _ in 0..1000 {
+            let mut board = Board::new();
+            while board.isover().is_none() {
+                let mv = random::Random.play(&board);
+                board.play(mv.square, mv.cell);
+
+                assert_eq!(
+                    *hashes.entry(board.key()).or_insert(board.hash()),
+                    board.hash()
+                );
+                assert_eq!(
+                    *keys.entry(board.hash()).or_insert(board.key()),
+                    board.key()
+                );
+            }
+        }
+    }
+}
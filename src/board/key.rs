@@ -1,9 +1,10 @@
+use super::{Rules, Symmetry};
 use crate::player;
 
 impl super::Board {
     /// All the mappings for symmetrical boards.
     #[rustfmt::skip]
-    const SYMMETRIES: [[usize; 25]; 7] = [
+    pub(super) const SYMMETRIES: [[usize; 25]; 7] = [
         // Flip diagonal 1-3:
         [24,21,22,23,20,9,6,7,8,5,14,11,12,13,10,19,16,17,18,15,4,1,2,3,0],
         // Flip diagonal 0-4:
@@ -20,19 +21,58 @@ impl super::Board {
         [6,9,7,5,8,21,24,22,20,23,11,14,12,10,13,1,4,2,0,3,16,19,17,15,18],
     ];
 
+    /// For every symmetry the square to which each square is mapped.
+    /// The cells within a square are permuted in the same way.
+    const SQUARES: [[u8; 5]; 7] = {
+        let mut squares = [[0; 5]; 7];
+        let mut symmetry = 0;
+        while symmetry < 7 {
+            let mut square = 0;
+            while square < 5 {
+                squares[symmetry][square] = (Self::SYMMETRIES[symmetry][square * 5] / 5) as u8;
+                square += 1;
+            }
+            symmetry += 1;
+        }
+        squares
+    };
+
+    /// For every symmetry the mapping of the 5 bits of a square, such that a
+    /// board can be mapped with one lookup per square instead of one per bit.
+    const CELLS: [[u32; 32]; 7] = {
+        let mut cells = [[0; 32]; 7];
+        let mut symmetry = 0;
+        while symmetry < 7 {
+            let mut bits = 0;
+            while bits < 32 {
+                let mut cell = 0;
+                while cell < 5 {
+                    if bits & 1 << cell != 0 {
+                        cells[symmetry][bits] |= 1 << Self::SQUARES[symmetry][cell];
+                    }
+                    cell += 1;
+                }
+                bits += 1;
+            }
+            symmetry += 1;
+        }
+        cells
+    };
+
     /// Indiciate whether the square of the lastmove should be in the key.
     /// This is the case if the square we need to move into next is full.
-    fn lastmove_square(&self) -> bool {
+    pub(super) fn lastmove_square(&self) -> bool {
         let (_, cell) = self.lastmove.unwrap();
         self.isfull(cell)
     }
 
-    /// Map the state or mask to the given symmetry.
-    fn symmetry_map(value: u32, symmetry: &[usize; 25]) -> u64 {
+    /// Map the state or mask to the symmetry with the given index in SYMMETRIES.
+    pub(super) fn symmetry_map(value: u32, symmetry: usize) -> u64 {
         let mut symmetry_value: u32 = 0;
-        for index in 0..25 {
-            let bit = (value & 1 << index) >> index;
-            symmetry_value += bit << symmetry[index];
+        for square in 0..5 {
+            let cells = (value >> (square * 5)) & 0b11111;
+            symmetry_value |=
+                Self::CELLS[symmetry][cells as usize] << (Self::SQUARES[symmetry][square] * 5);
         }
         symmetry_value as u64
     }
@@ -64,50 +104,59 @@ impl super::Board {
         key
     }
 
-    /// Return all u64 uniquely identifying this equivalence class of the board.
-    // TODO convert this to an iterator implementation
-    pub fn keys(&self) -> [u64; 8] {
-        let mut keys: [u64; 8] = [0; 8];
-
-        // The board is completely empty (so lastmove == None).
-        if self.isfirst() {
-            return keys;
+    /// Return the key of the board transformed by the given symmetry,
+    /// without actually transforming the board.
+    fn symmetry_key(&self, symmetry: Symmetry) -> u64 {
+        if symmetry == Symmetry::Identity || self.isfirst() {
+            return self.key();
         }
 
-        keys[0] = self.key();
-        let lastmove_square = self.lastmove_square();
+        let mut key: u64 = 0;
 
-        // Generate the keys for all the symmetries.
-        for index in 0..7 {
-            // Add the player onturn and the takestreak.
-            keys[index + 1] += (self.onturn as u64) << 63;
-            keys[index + 1] += (self.takestreak as u64) << 56;
+        // Add the player onturn and the takestreak.
+        key += (self.onturn as u64) << 63;
+        key += (self.takestreak as u64) << 56;
 
-            // Add the lastmove to the key. Create the lastmove symmetry.
-            let lastmove_index = self.lastmove.unwrap().0 * 5 + self.lastmove.unwrap().1;
-            let lastmove_symmetry = (
-                Self::SYMMETRIES[index][lastmove_index as usize] / 5,
-                Self::SYMMETRIES[index][lastmove_index as usize] % 5,
-            );
+        // Add the cell and the square of the symmetrical lastmove to the key.
+        let (square, cell) = self.lastmove.unwrap();
+        let (square, cell) = symmetry.map(square, cell);
+        key += (cell as u64) << 50;
+        if self.lastmove_square() {
+            key += (square as u64) << 53;
+        }
 
-            // Add the cell and the square to the key.
-            keys[index + 1] += (lastmove_symmetry.1 as u64) << 50; // cell
-            if lastmove_square {
-                keys[index + 1] += (lastmove_symmetry.0 as u64) << 53; // square
-            }
+        // Add the mask and the state.
+        let index = symmetry.index() - 1;
+        key += Self::symmetry_map(self.mask, index) << 25;
+        key += Self::symmetry_map(self.state, index);
 
-            // Add the mask and the state.
-            keys[index + 1] += Self::symmetry_map(self.mask, &Self::SYMMETRIES[index]) << 25;
-            keys[index + 1] += Self::symmetry_map(self.state, &Self::SYMMETRIES[index]);
-        }
+        key
+    }
 
-        keys
+    /// Return all u64 uniquely identifying this equivalence class of the board,
+    /// in the order of Symmetry::ALL. The keys are only computed when needed.
+    pub fn keys(&self) -> impl Iterator<Item = u64> + '_ {
+        Symmetry::ALL
+            .iter()
+            .map(move |&symmetry| self.symmetry_key(symmetry))
     }
 
-    /// Create a board from a key. Note that no checks on correctness are made.
+    /// Return the smallest key of this equivalence class of the board, which
+    /// is the same for all the symmetrical boards.
+    pub fn canonical_key(&self) -> u64 {
+        self.keys().min().unwrap()
+    }
+
+    /// Create a board from a key. Note that no checks on correctness are made,
+    /// use validate() to check the board if the key is not trusted.
     /// Important: the movecount is set to 0 if no moves are made and 1 otherwise.
-    pub fn from_key(mut key: u64) -> Self {
-        let mut board = Self::new();
+    pub fn from_key(key: u64) -> Self {
+        Self::from_key_with_rules(key, Rules::default())
+    }
+
+    /// Create a board from a key of a board on which is played with the given rules.
+    pub fn from_key_with_rules(mut key: u64, rules: Rules) -> Self {
+        let mut board = Self::with_rules(rules);
 
         // Return early if the board is empty (this is neccessary because of lastmove).
         if key == 0 {
@@ -142,28 +191,15 @@ impl super::Board {
         // Set the movecount.
         board.movecount = if board.mask == 0 { 0 } else { 1 };
 
-        // Count the number of stones a player has left, by counting the number of stones placed.
-        let count_stones = |state: u32| {
-            let mut count = state.count_ones() as u8;
-            // Stones at the double positions are counted double...
-            let doubles = [
-                0b0000000000100000000000000,
-                0b0000000000010000000000000,
-                0b0000000000000100000000000,
-                0b0000000000000010000000000,
-            ];
-            for double in doubles {
-                if state & double > 0 {
-                    count -= 1;
-                }
-            }
-            Self::NUMBER_OF_STONES - count
-        };
-
         // Deduce the number of stones each player has left.
-        board.stones[board.onturn as usize] = count_stones(board.state);
-        board.stones[1 ^ board.onturn as usize] = count_stones(board.state ^ board.mask);
-
+        // Use a saturating subtraction, such that invalid keys are caught by validate().
+        board.stones[board.onturn as usize] =
+            rules.stones().saturating_sub(Self::placed(board.state));
+        board.stones[1 ^ board.onturn as usize] = rules
+            .stones()
+            .saturating_sub(Self::placed(board.state ^ board.mask));
+
+        board.rehash();
         board
     }
 }
@@ -190,7 +226,23 @@ mod tests {
     fn symmetry_map() {
         let v1: u32 = 0b00001_00010_11010_01011_01100;
         let s1: u64 = 0b00110_11010_01011_01000_10000;
-        assert_eq!(s1, Board::symmetry_map(v1, &Board::SYMMETRIES[5]))
+        assert_eq!(s1, Board::symmetry_map(v1, 5))
+    }
+
+    /// Test whether the lookup tables map every bit like the symmetries.
+    #[test]
+    fn symmetry_map_tables() {
+        for (index, symmetry) in Board::SYMMETRIES.iter().enumerate() {
+            for (bit, &mapped) in symmetry.iter().enumerate() {
+                assert_eq!(
+                    Board::symmetry_map(1 << bit, index),
+                    1 << mapped,
+                    "symmetry {} bit {}",
+                    index,
+                    bit
+                );
+            }
+        }
     }
 
     /// Test first move key generation.
@@ -226,7 +278,7 @@ mod tests {
     #[test]
     fn keys() {
         let board1 = Board::load("221400203101122").unwrap();
-        let keys1 = board1.keys();
+        let keys1: Vec<u64> = board1.keys().collect();
 
         assert_eq!(
             keys1[0],
@@ -239,7 +291,7 @@ mod tests {
         );
 
         let board2 = Board::load("221400203101123").unwrap();
-        let keys2 = board2.keys();
+        let keys2: Vec<u64> = board2.keys().collect();
 
         assert_eq!(
             keys2[0],
@@ -252,7 +304,7 @@ mod tests {
         );
 
         let board3 = Board::load("2214002031011232").unwrap();
-        let keys3 = board3.keys();
+        let keys3: Vec<u64> = board3.keys().collect();
 
         assert_eq!(
             keys3[0],
@@ -269,12 +321,14 @@ mod tests {
     #[test]
     fn keys_firstmove() {
         let board1 = Board::new();
+        assert_eq!(board1.keys().count(), 8);
         for key in board1.keys() {
             assert_eq!(key, 0);
         }
+        assert_eq!(board1.canonical_key(), 0);
 
         let board2 = Board::load("21").unwrap();
-        let keys2 = board2.keys();
+        let keys2: Vec<u64> = board2.keys().collect();
 
         assert_eq!(
             keys2[0],
@@ -313,4 +367,120 @@ mod tests {
             );
         }
     }
+
+    /// Test whether every symmetry is a permutation which maps squares to squares.
+    /// A symmetry is fully determined by how it permutes the squares, as the cell
+    /// of a move indicates the next square.
+    #[test]
+    fn symmetries_structure() {
+        for symmetry in Board::SYMMETRIES.iter() {
+            let mut sorted = symmetry.to_vec();
+            sorted.sort();
+            assert_eq!(sorted, (0..25).collect::<Vec<_>>(), "not a permutation");
+
+            let squares: Vec<usize> = (0..5).map(|square| symmetry[square * 5] / 5).collect();
+            assert_eq!(squares[2], 2, "the center square should stay in place");
+            for square in 0..5 {
+                for cell in 0..5 {
+                    assert_eq!(
+                        symmetry[square * 5 + cell],
+                        squares[square] * 5 + squares[cell]
+                    );
+                }
+            }
+        }
+    }
+
+    /// Test whether the double cells are mapped onto double cells.
+    #[test]
+    fn symmetries_double() {
+        for symmetry in Symmetry::ALL {
+            for square in 0..5 {
+                for cell in 0..5 {
+                    let (s, c) = symmetry.map(square, cell);
+                    match Board::double(square, cell) {
+                        None => assert_eq!(Board::double(s, c), None),
+                        Some((ds, dc)) => {
+                            assert_eq!(Board::double(s, c), Some(symmetry.map(ds, dc)))
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Test whether the identity together with the symmetries forms a group.
+    #[test]
+    fn symmetries_group() {
+        let identity: [usize; 25] = {
+            let mut identity = [0; 25];
+            for (index, value) in identity.iter_mut().enumerate() {
+                *value = index;
+            }
+            identity
+        };
+
+        let mut group = vec![identity];
+        group.extend(Board::SYMMETRIES.iter().cloned());
+
+        for a in &group {
+            // Every element should have its inverse in the group.
+            let mut inverse = [0; 25];
+            for index in 0..25 {
+                inverse[a[index]] = index;
+            }
+            assert!(group.contains(&inverse), "missing inverse");
+
+            // The group should be closed under composition.
+            for b in &group {
+                let mut composition = [0; 25];
+                for index in 0..25 {
+                    composition[index] = b[a[index]];
+                }
+                assert!(group.contains(&composition), "not closed");
+            }
+        }
+
+        // All the elements should be distinct.
+        for i in 0..group.len() {
+            for j in i + 1..group.len() {
+                assert_ne!(group[i], group[j]);
+            }
+        }
+    }
+
+    /// Test whether the possible moves are the same for symmetrical boards.
+    #[test]
+    fn symmetries_moves() {
+        for _ in 0..1000 {
+            let board = Board::random();
+            if board.isfirst() || board.isover().is_some() {
+                continue;
+            }
+
+            let moves = board.moves();
+            for symmetry in Symmetry::ALL {
+                let mut expected: Vec<_> = moves
+                    .iter()
+                    .map(|mv| Move::from(symmetry.map(mv.square, mv.cell)))
+                    .collect();
+                let mut actual = board.transform(symmetry).moves();
+
+                expected.sort();
+                actual.sort();
+                assert_eq!(expected, actual, "{:?}", symmetry);
+            }
+        }
+    }
+
+    /// Test whether the game over detection is the same for symmetrical boards.
+    #[test]
+    fn symmetries_isover() {
+        for _ in 0..1000 {
+            let board = Board::random();
+            for symmetry in Symmetry::ALL {
+                assert_eq!(board.isover(), board.transform(symmetry).isover());
+            }
+        }
+    }
 }
@@ -1,9 +1,27 @@
-use crate::{player, player::Player, random};
+use crate::{error, player, player::Player, random};
 use rand::Rng;
 
+mod fen;
+mod hash;
 mod key;
-
-#[derive(Debug, Eq, PartialEq)]
+mod moves;
+mod position;
+mod rules;
+mod symmetry;
+mod validate;
+
+pub use moves::{Move, MoveError, Moves};
+pub use position::Position;
+pub use rules::Rules;
+pub use symmetry::Symmetry;
+pub use validate::Invalid;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum Result {
     /// Use this if player1 has won.
     Player1,
@@ -30,7 +48,67 @@ impl Result {
     }
 }
 
-#[derive(Debug, Clone)]
+/// Why the game is over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Termination {
+    /// The lastmove completed a square.
+    Square,
+    /// The board is full, the majority in the squares decides.
+    FullBoard,
+    /// Stones were taken too many turns in a row, which is a draw.
+    Takestreak,
+    /// The player onturn has no stones left.
+    NoStones,
+}
+
+impl std::fmt::Display for Termination {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Termination::Square => write!(f, "square"),
+            Termination::FullBoard => write!(f, "full board"),
+            Termination::Takestreak => write!(f, "takestreak"),
+            Termination::NoStones => write!(f, "no stones"),
+        }
+    }
+}
+
+impl std::str::FromStr for Termination {
+    type Err = error::Error;
+
+    fn from_str(text: &str) -> error::Result<Self> {
+        match text {
+            "square" => Ok(Termination::Square),
+            "full board" => Ok(Termination::FullBoard),
+            "takestreak" => Ok(Termination::Takestreak),
+            "no stones" => Ok(Termination::NoStones),
+            _ => Err(error::Error::Notation(format!(
+                "Unknown termination {}.",
+                text
+            ))),
+        }
+    }
+}
+
+/// The information needed to take back a move, see Board::unplay().
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Undo {
+    /// The (square, cell) at which the move was made.
+    square: u8,
+    cell: u8,
+    /// Whether a stone of the opponent was taken.
+    taken: bool,
+    /// The lastmove before the move was made.
+    lastmove: Option<(u8, u8)>,
+    /// The takestreak before the move was made.
+    takestreak: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Board {
     /// The places at which the player onturn has a stone.
     /// 0b00000_00000_00000_00000_00000
@@ -59,74 +137,86 @@ pub struct Board {
     /// The number of moves that have been made.
     // This is an i16 because this make eval::Eval more efficient.
     movecount: i16,
+
+    /// The hashes of the cells of the board and its symmetries, in the order
+    /// of Symmetry::ALL. These are updated by play and unplay, see hash().
+    hashes: [u64; 8],
+
+    /// The rules of the game played on this board.
+    rules: Rules,
 }
 
 impl Board {
-    /// The maximum number of takes that are allowed to be made in a row.
+    /// The maximum number of takes that are allowed to be made in a row
+    /// with the default rules.
     // Make sure this number fits in 7 bits so it fits in the key.
     pub const TAKESTREAK_LIMIT: u8 = 15; // <= 127
 
-    /// An upperbound on the maximum number of moves that can be made in a game.
+    /// An upperbound on the maximum number of moves that can be made in a game
+    /// with the default rules. No rules can have a higher limit, see Rules::new.
     pub const MOVECOUNT_LIMIT: i16 = (21 - 7) * Self::TAKESTREAK_LIMIT as i16;
 
-    /// The number of stones each player starts with.
+    /// The number of stones each player starts with with the default rules.
     pub const NUMBER_OF_STONES: u8 = 12;
 
     /// Create a new empty board.
     pub fn new() -> Self {
+        Self::with_rules(Rules::default())
+    }
+
+    /// Create a new empty board on which is played with the given rules.
+    pub fn with_rules(rules: Rules) -> Self {
         Self {
             state: 0,
             mask: 0,
 
             onturn: player::Players::Player1,
-            stones: [Self::NUMBER_OF_STONES, Self::NUMBER_OF_STONES],
+            stones: [rules.stones(), rules.stones()],
 
             lastmove: None,
             takestreak: 0,
 
             movecount: 0,
+
+            hashes: [0; 8],
+
+            rules,
         }
     }
 
     /// Create a board with the specified configuration.
-    pub fn load(moves: &str) -> std::result::Result<Self, String> {
-        let mut board = Self::new();
+    pub fn load(moves: &str) -> error::Result<Self> {
+        Self::load_with_rules(moves, Rules::default())
+    }
+
+    /// Create a board with the specified configuration using the given rules.
+    pub fn load_with_rules(moves: &str, rules: Rules) -> error::Result<Self> {
+        let mut board = Self::with_rules(rules);
 
         if !moves.chars().all(|c| '0' <= c && c <= '4') {
-            return Err("Please only use the digits 0 to 4.".to_string());
+            return Err(error::Error::Notation(
+                "Please only use the digits 0 to 4.".to_string(),
+            ));
         }
 
         if moves.len() == 0 {
             return Ok(board);
         } else if moves.len() == 1 {
-            return Err("Please provide the square for the first move.".to_string());
+            return Err(error::Error::Notation(
+                "Please provide the square for the first move.".to_string(),
+            ));
         }
 
-        let mut cs = moves.chars().map(|c: char| c as u8 - '0' as u8);
-        board.play(cs.next().unwrap(), cs.next().unwrap());
-
-        let mut gamefinished = false;
-        for c in cs {
-            if gamefinished {
-                return Err(format!(
-                    "Move {} is invalid: ({}, {}), game finished.",
-                    board.movecount() + 1,
-                    board.lastmove.unwrap().1,
-                    c
-                ));
-            } else if board.canplay(board.square().unwrap(), c) {
-                board.play(board.square().unwrap(), c);
-                gamefinished = board.isover() != None;
-            } else {
-                return Err(format!(
-                    "Move {} is invalid: ({}, {}).",
-                    board.movecount() + 1,
-                    board.lastmove.unwrap().1,
-                    c
-                ));
-            }
+        // Every move after the first is played in the square of the previous cell.
+        let mut cells = moves.bytes().map(|c| c - b'0');
+        let square = cells.next().unwrap();
+        let mut mv = Move::new(square, cells.next().unwrap());
+        board.try_play(mv)?;
+        for cell in cells {
+            mv = Move::new(mv.cell, cell);
+            board.try_play(mv)?;
         }
-        return Ok(board);
+        Ok(board)
     }
 
     /// Create a random board position which may or may not be game over.
@@ -137,8 +227,8 @@ impl Board {
         let mut board = Self::new();
 
         for _ in 0..rng.gen_range(0..=Board::MOVECOUNT_LIMIT) {
-            let (square, cell) = player.play(&board);
-            board.play(square, cell);
+            let mv = player.play(&board);
+            board.play(mv.square, mv.cell);
 
             if board.isover() != None {
                 break;
@@ -164,6 +254,24 @@ impl Board {
         }
     }
 
+    /// Count the number of stones placed on the given cells.
+    /// Stones at the double positions are only counted once.
+    fn placed(cells: u32) -> u8 {
+        let mut count = cells.count_ones() as u8;
+        let doubles = [
+            0b0000000000100000000000000,
+            0b0000000000010000000000000,
+            0b0000000000000100000000000,
+            0b0000000000000010000000000,
+        ];
+        for double in doubles {
+            if cells & double > 0 {
+                count -= 1;
+            }
+        }
+        count
+    }
+
     /// Check if the onturn player can play on (square, cell).
     pub fn canplay(&self, square: u8, cell: u8) -> bool {
         debug_assert!(self.lastmove == None || self.lastmove.unwrap().1 == square);
@@ -171,6 +279,11 @@ impl Board {
         debug_assert!(square < 5 && cell < 5);
         debug_assert!(self.isover() == None);
 
+        // The first move may not be in the center square with some rules.
+        if self.isfirst() && self.rules.first_move_restricted() && square == 2 {
+            return false;
+        }
+
         let bit = 1 << square * 5 + cell;
 
         // If the cell is empty, return true. Most will return here.
@@ -205,20 +318,35 @@ impl Board {
     }
 
     /// Make the play at the given (square, cell).
-    pub fn play(&mut self, square: u8, cell: u8) {
+    /// Returns the information needed to take the move back with unplay().
+    pub fn play(&mut self, square: u8, cell: u8) -> Undo {
         debug_assert!(self.canplay(square, cell));
 
+        let taken = self.mask & 1 << square * 5 + cell != 0;
+        let undo = Undo {
+            square,
+            cell,
+            taken,
+            lastmove: self.lastmove,
+            takestreak: self.takestreak,
+        };
+
         // Check if we take a stone from the opponent.
-        if self.mask & 1 << square * 5 + cell != 0 {
+        if taken {
             self.stones[self.onturn.other() as usize] += 1;
             self.takestreak += 1;
         } else if self.takestreak > 0 {
             self.takestreak = 0;
         }
 
-        // Update the state and mask variable according to the move.
+        // Update the state, mask and hashes according to the move.
+        let onturn = self.onturn;
         let mut update = |square: u8, cell: u8| {
             let bit = 1 << square * 5 + cell;
+            if taken {
+                self.toggle_hashes(square * 5 + cell, onturn.other());
+            }
+            self.toggle_hashes(square * 5 + cell, onturn);
             self.state ^= bit;
             self.mask |= bit;
         };
@@ -237,10 +365,61 @@ impl Board {
         self.state ^= self.mask;
         self.lastmove = Some((square, cell));
         self.movecount += 1;
+
+        undo
+    }
+
+    /// Take back the move made by play(), this should be the last move made.
+    pub fn unplay(&mut self, undo: Undo) {
+        debug_assert!(self.lastmove == Some((undo.square, undo.cell)));
+
+        // Go back to the perspective of the player who made the move.
+        self.onturn = self.onturn.other();
+        self.state ^= self.mask;
+
+        // Remove the stone, or give the cell back to the opponent if it was taken.
+        let onturn = self.onturn;
+        let mut revert = |square: u8, cell: u8| {
+            let bit = 1 << square * 5 + cell;
+            self.toggle_hashes(square * 5 + cell, onturn);
+            if undo.taken {
+                self.toggle_hashes(square * 5 + cell, onturn.other());
+            }
+            self.state ^= bit;
+            if !undo.taken {
+                self.mask ^= bit;
+            }
+        };
+
+        // Revert the cell and the double cell if we are in one.
+        revert(undo.square, undo.cell);
+        if let Some((s, c)) = Self::double(undo.square, undo.cell) {
+            revert(s, c);
+        }
+
+        // Restore the stones, lastmove, takestreak and movecount.
+        self.stones[self.onturn as usize] += 1;
+        if undo.taken {
+            self.stones[self.onturn.other() as usize] -= 1;
+        }
+        self.lastmove = undo.lastmove;
+        self.takestreak = undo.takestreak;
+        self.movecount -= 1;
     }
 
     /// Check if the game is over, as a result of the lastmove!
     pub fn isover(&self) -> Option<Result> {
+        self.outcome().map(|(result, _)| result)
+    }
+
+    /// Return why the game is over, if it is.
+    pub fn termination(&self) -> Option<Termination> {
+        self.outcome().map(|(_, termination)| termination)
+    }
+
+    /// Check if the game is over and if so return the result and why.
+    #[inline]
+    fn outcome(&self) -> Option<(Result, Termination)> {
         // No one can win within just 8 moves, at least 9 are needed.
         if self.movecount <= 8 {
             return None;
@@ -255,12 +434,18 @@ impl Board {
         // Check if the (previous) player has finished a square.
         // Also check the double square if applicable.
         if check_square(self.lastmove.unwrap().0) {
-            return Some(Result::from_player(&self.onturn.other()));
+            return Some((
+                Result::from_player(&self.onturn.other()),
+                Termination::Square,
+            ));
         }
         if let Some((square, _)) = Self::double(self.lastmove.unwrap().0, self.lastmove.unwrap().1)
         {
             if check_square(square) {
-                return Some(Result::from_player(&self.onturn.other()));
+                return Some((
+                    Result::from_player(&self.onturn.other()),
+                    Termination::Square,
+                ));
             }
         }
 
@@ -277,32 +462,41 @@ impl Board {
                     .count()
             };
             match square_count_onturn {
-                c if c > 2 => return Some(Result::from_player(&self.onturn)),
-                _ => return Some(Result::from_player(&self.onturn.other())),
+                c if c >= self.rules.full_board_squares() as usize => {
+                    return Some((Result::from_player(&self.onturn), Termination::FullBoard))
+                }
+                _ => {
+                    return Some((
+                        Result::from_player(&self.onturn.other()),
+                        Termination::FullBoard,
+                    ))
+                }
             }
         }
 
         // The streak of consecutively taking stones is reached.
-        if self.takestreak == Self::TAKESTREAK_LIMIT {
-            return Some(Result::Draw);
+        if self.takestreak == self.rules.takestreak_limit() {
+            return Some((Result::Draw, Termination::Takestreak));
         }
 
         // Check if the player onturn still has stones left.
         if self.stones[self.onturn as usize] == 0 {
-            return Some(Result::from_player(&self.onturn));
+            return Some((Result::from_player(&self.onturn), Termination::NoStones));
         }
 
         return None; // The game is not over yet.
     }
 
-    /// Return all the moves that can be made from the current position as (square, cell).
-    pub fn moves(&self) -> Vec<(u8, u8)> {
+    /// Return all the moves that can be made from the current position.
+    pub fn moves(&self) -> Vec<Move> {
         if self.isfirst() {
-            vec![(0, 0), (0, 1), (0, 2), (0, 4), (2, 0), (2, 2)]
+            self.ordered().collect()
         } else {
-            (0..=4)
-                .map(|cell| (self.square().unwrap(), cell))
-                .filter(|&(square, cell)| self.canplay(square, cell))
+            let square = self.square().unwrap();
+            let legal = self.legal_moves_mask();
+            (0..5)
+                .filter(|cell| legal & 1 << cell != 0)
+                .map(|cell| Move::new(square, cell))
                 .collect()
         }
     }
@@ -322,6 +516,16 @@ impl Board {
         self.onturn
     }
 
+    /// Return the rules of the game played on this board.
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    /// Return the number of stones left for the given player.
+    pub fn stones(&self, player: player::Players) -> u8 {
+        self.stones[player as usize]
+    }
+
     /// Return if this will be the first move.
     pub fn isfirst(&self) -> bool {
         self.movecount == 0
@@ -625,6 +829,7 @@ mod tests {
 
         let board2 = Board::load("22021232422").unwrap();
         assert_eq!(board2.isover(), Some(Result::Player2));
+        assert_eq!(board2.termination(), Some(Termination::Square));
     }
 
     /// Test winning on a full board.
@@ -635,6 +840,7 @@ mod tests {
 
         let board2 = Board::load("2003310221243201141030223442").unwrap();
         assert_eq!(board2.isover(), Some(Result::Player1));
+        assert_eq!(board2.termination(), Some(Termination::FullBoard));
     }
 
     /// Test drawing because the takestreak is reached.
@@ -652,6 +858,7 @@ mod tests {
 
         let board = Board::load(&(start + cycle)).unwrap();
         assert_eq!(board.isover(), Some(Result::Draw));
+        assert_eq!(board.termination(), Some(Termination::Takestreak));
     }
 
     /// Test winning because the player onturn has no stones left.
@@ -659,6 +866,20 @@ mod tests {
     fn isover_stones() {
         let board = Board::load("0020301101440313322423412").unwrap();
         assert_eq!(board.isover(), Some(Result::Player1));
+        assert_eq!(board.termination(), Some(Termination::NoStones));
+        assert_eq!(Board::new().termination(), None);
+
+        for termination in [
+            Termination::Square,
+            Termination::FullBoard,
+            Termination::Takestreak,
+            Termination::NoStones,
+        ] {
+            assert_eq!(
+                termination.to_string().parse::<Termination>().unwrap(),
+                termination
+            );
+        }
     }
 
     /// Test winning in the fastest possible way.
@@ -744,4 +965,44 @@ mod tests {
         assert_ne!(board.stones[1], board_clone.stones[1]);
         assert_ne!(board.lastmove, board_clone.lastmove);
     }
+
+    /// Test whether unplay restores the board exactly as it was before play.
+    #[test]
+    fn unplay() {
+        for _ in 0..1000 {
+            let mut board = Board::new();
+            while board.isover().is_none() {
+                let before = board.clone();
+                let mv = random::Random.play(&board);
+
+                let undo = board.play(mv.square, mv.cell);
+                let after = board.clone();
+
+                board.unplay(undo);
+                assert_eq!(board, before);
+
+                board.play(mv.square, mv.cell);
+                assert_eq!(board, after);
+            }
+        }
+    }
+
+    /// Test whether results and terminations are serialized and read again.
+    #[cfg(feature = "serde")]
+    #[test]
+    fn result_serde() {
+        let json = serde_json::to_string(&Result::Player2).unwrap();
+        assert_eq!(json, "\"player2\"");
+        assert_eq!(
+            serde_json::from_str::<Result>(&json).unwrap(),
+            Result::Player2
+        );
+
+        let json = serde_json::to_string(&Termination::FullBoard).unwrap();
+        assert_eq!(json, "\"full_board\"");
+        assert_eq!(
+            serde_json::from_str::<Termination>(&json).unwrap(),
+            Termination::FullBoard
+        );
+    }
 }
@@ -0,0 +1,392 @@
+use super::Board;
+
+/// A move which places a stone on the given cell in the given square.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Move {
+    pub square: u8,
+    pub cell: u8,
+}
+
+/// The reason why a move cannot be made.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveError {
+    /// The text is not of the form [0-4]<0-4>.
+    Format,
+    /// The first move needs both a square and a cell.
+    MissingSquare,
+    /// The move is not in the square constraint (expected, found).
+    Square(u8, u8),
+    /// The cell already contains a stone of the player onturn.
+    OwnStone,
+    /// The cell contains a stone of the opponent, but the square is not full.
+    SquareNotFull,
+    /// The cell contains the stone of the lastmove, which cannot be taken back
+    /// unless it is the only option.
+    Lastmove,
+    /// The game is already over.
+    GameOver,
+    /// The first move cannot be in the center square with the current rules.
+    Restricted,
+}
+
+impl std::fmt::Display for MoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MoveError::Format => write!(f, "Please use the move format: [0-4]<0-4>."),
+            MoveError::MissingSquare => write!(f, "Please also provide the square."),
+            MoveError::Square(expected, found) => write!(
+                f,
+                concat!(
+                    "The square should be {}, not {}.\n",
+                    "Hint: you don't have to specify the square.",
+                ),
+                expected, found
+            ),
+            MoveError::OwnStone => write!(f, "The cell already contains your stone."),
+            MoveError::SquareNotFull => write!(f, "You can only take stones from full squares."),
+            MoveError::Lastmove => write!(f, "You cannot take the stone just played."),
+            MoveError::GameOver => write!(f, "The game already finished."),
+            MoveError::Restricted => write!(f, "The first move cannot be in the center square."),
+        }
+    }
+}
+
+impl std::error::Error for MoveError {}
+
+impl Move {
+    /// Create a move on the given (square, cell).
+    pub const fn new(square: u8, cell: u8) -> Self {
+        debug_assert!(square < 5 && cell < 5);
+        Self { square, cell }
+    }
+
+    /// Parse a move of the form [0-4]<0-4>, the square is taken from the square
+    /// constraint of the board if it is not given. The move is not checked.
+    pub fn parse(board: &Board, text: &str) -> std::result::Result<Self, MoveError> {
+        let digits: Vec<u8> = text
+            .chars()
+            .map(|c| match c {
+                '0'..='4' => Ok(c as u8 - b'0'),
+                _ => Err(MoveError::Format),
+            })
+            .collect::<std::result::Result<_, _>>()?;
+
+        match (digits.len(), board.square()) {
+            (1, None) => Err(MoveError::MissingSquare),
+            (1, Some(square)) => Ok(Self::new(square, digits[0])),
+            (2, _) => Ok(Self::new(digits[0], digits[1])),
+            (_, _) => Err(MoveError::Format),
+        }
+    }
+}
+
+impl std::str::FromStr for Move {
+    type Err = MoveError;
+
+    /// Parse a move of the form [0-4][0-4].
+    fn from_str(text: &str) -> std::result::Result<Self, Self::Err> {
+        match text.as_bytes() {
+            &[square @ b'0'..=b'4', cell @ b'0'..=b'4'] => {
+                Ok(Self::new(square - b'0', cell - b'0'))
+            }
+            _ => Err(MoveError::Format),
+        }
+    }
+}
+
+impl std::fmt::Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.square, self.cell)
+    }
+}
+
+impl From<(u8, u8)> for Move {
+    fn from((square, cell): (u8, u8)) -> Self {
+        Self::new(square, cell)
+    }
+}
+
+impl From<Move> for (u8, u8) {
+    fn from(mv: Move) -> Self {
+        (mv.square, mv.cell)
+    }
+}
+
+/// An iterator over at most six moves which does not allocate, see Board::ordered().
+#[derive(Debug, Clone)]
+pub struct Moves {
+    moves: [Move; 6],
+    len: usize,
+    index: usize,
+}
+
+impl Moves {
+    /// The first moves, all other first moves are symmetrical to one of these.
+    const FIRST: [Move; 6] = [
+        Move::new(0, 0),
+        Move::new(0, 1),
+        Move::new(0, 2),
+        Move::new(0, 4),
+        Move::new(2, 0),
+        Move::new(2, 2),
+    ];
+}
+
+impl Iterator for Moves {
+    type Item = Move;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index < self.len {
+            self.index += 1;
+            Some(self.moves[self.index - 1])
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len - self.index, Some(self.len - self.index))
+    }
+}
+
+impl ExactSizeIterator for Moves {}
+
+impl Board {
+    /// Return the cells in the square constraint which can be played as a bitmask,
+    /// bit i is set if cell i can be played. This gives the same as canplay(),
+    /// so it should not be used for the first move or when the game is over.
+    pub fn legal_moves_mask(&self) -> u8 {
+        debug_assert!(!self.isfirst());
+
+        let (lastsquare, lastcell) = self.lastmove.unwrap();
+        let square = lastcell;
+        let shift = square * 5;
+
+        let mask = (self.mask >> shift) as u8 & 0b11111;
+        let state = (self.state >> shift) as u8 & 0b11111;
+
+        // If the square is not full, all the empty cells can be played.
+        if mask != 0b11111 {
+            return !mask & 0b11111;
+        }
+
+        // Otherwise all the stones of the opponent can be taken, except the
+        // lastmove (or its double) if there are other options.
+        let takes = !state & 0b11111;
+        let lastmove = match (lastsquare, Self::double(lastsquare, lastcell)) {
+            (s, _) if s == square => 1 << lastcell,
+            (_, Some((s, c))) if s == square => 1 << c,
+            (_, _) => 0,
+        };
+
+        if takes & !lastmove != 0 {
+            takes & !lastmove
+        } else {
+            takes
+        }
+    }
+
+    /// Return a bitmask of the full squares, bit i is set if square i is full.
+    pub fn full_squares_mask(&self) -> u8 {
+        (0..5)
+            .filter(|&square| self.isfull(square))
+            .fold(0, |full, square| full | 1 << square)
+    }
+
+    /// Return the possible moves without allocating. Cells that go to squares
+    /// which are not full are put up front, as these are often better.
+    pub fn ordered(&self) -> Moves {
+        let mut moves = Moves {
+            moves: Moves::FIRST,
+            len: Moves::FIRST.len(),
+            index: 0,
+        };
+
+        if self.isfirst() {
+            if self.rules.first_move_restricted() {
+                moves.len = 0;
+                for mv in Moves::FIRST.iter().filter(|mv| mv.square != 2) {
+                    moves.moves[moves.len] = *mv;
+                    moves.len += 1;
+                }
+            }
+            return moves;
+        }
+
+        let square = self.square().unwrap();
+        let legal = self.legal_moves_mask();
+        let full = self.full_squares_mask();
+
+        moves.len = 0;
+        for cells in [legal & !full, legal & full] {
+            for cell in 0..5 {
+                if cells & 1 << cell != 0 {
+                    moves.moves[moves.len] = Move::new(square, cell);
+                    moves.len += 1;
+                }
+            }
+        }
+        moves
+    }
+
+    /// Check whether the given move can be made and if not why.
+    pub fn check(&self, mv: Move) -> std::result::Result<(), MoveError> {
+        if self.isover().is_some() {
+            return Err(MoveError::GameOver);
+        }
+        if mv.square >= 5 || mv.cell >= 5 {
+            return Err(MoveError::Format);
+        }
+        if let Some(square) = self.square() {
+            if square != mv.square {
+                return Err(MoveError::Square(square, mv.square));
+            }
+        } else if self.rules.first_move_restricted() && mv.square == 2 {
+            return Err(MoveError::Restricted);
+        }
+        if self.canplay(mv.square, mv.cell) {
+            return Ok(());
+        }
+
+        // Find out why the move could not be made.
+        let bit = 1 << (mv.square * 5 + mv.cell);
+        if self.state & bit != 0 {
+            Err(MoveError::OwnStone)
+        } else if !self.isfull(mv.square) {
+            Err(MoveError::SquareNotFull)
+        } else {
+            Err(MoveError::Lastmove)
+        }
+    }
+
+    /// Make the given move if it is legal, otherwise leave the board untouched.
+    pub fn try_play(&mut self, mv: Move) -> std::result::Result<(), MoveError> {
+        self.check(mv)?;
+        self.play(mv.square, mv.cell);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test parsing and formatting moves.
+    #[test]
+    fn parse() {
+        assert_eq!("23".parse::<Move>(), Ok(Move::new(2, 3)));
+        assert_eq!("2".parse::<Move>(), Err(MoveError::Format));
+        assert_eq!("25".parse::<Move>(), Err(MoveError::Format));
+        assert_eq!("234".parse::<Move>(), Err(MoveError::Format));
+        assert_eq!(Move::new(4, 0).to_string(), "40");
+
+        let board = Board::load("23").unwrap();
+        assert_eq!(Move::parse(&board, "1"), Ok(Move::new(3, 1)));
+        assert_eq!(Move::parse(&board, "31"), Ok(Move::new(3, 1)));
+        assert_eq!(Move::parse(&board, "x"), Err(MoveError::Format));
+        assert_eq!(
+            Move::parse(&Board::new(), "1"),
+            Err(MoveError::MissingSquare)
+        );
+    }
+
+    /// Test the reasons for illegal moves.
+    #[test]
+    fn try_play() {
+        let mut board1 = Board::load("00").unwrap();
+        assert_eq!(
+            board1.try_play(Move::new(1, 1)),
+            Err(MoveError::Square(0, 1))
+        );
+        assert_eq!(
+            board1.try_play(Move::new(0, 0)),
+            Err(MoveError::SquareNotFull)
+        );
+        assert_eq!(board1.try_play(Move::new(0, 1)), Ok(()));
+        assert_eq!(board1.movecount(), 2);
+
+        let mut board2 = Board::load("00203010").unwrap();
+        assert_eq!(board2.try_play(Move::new(0, 1)), Err(MoveError::OwnStone));
+
+        let mut board3 = Board::load("12101411").unwrap();
+        assert_eq!(board3.try_play(Move::new(1, 1)), Err(MoveError::Lastmove));
+
+        let mut board4 = Board::load("2320212422").unwrap();
+        assert_eq!(board4.try_play(Move::new(2, 0)), Err(MoveError::GameOver));
+        assert_eq!(board4.movecount(), 9);
+    }
+
+    /// Count the number of move sequences of the given length using canplay().
+    fn perft_canplay(board: &mut Board, depth: usize) -> usize {
+        if depth == 0 || board.isover().is_some() {
+            return 1;
+        }
+        let mut count = 0;
+        for square in 0..5 {
+            for cell in 0..5 {
+                if board.isfirst() && !Moves::FIRST.contains(&Move::new(square, cell)) {
+                    continue;
+                }
+                if !board.isfirst()
+                    && (square != board.square().unwrap() || !board.canplay(square, cell))
+                {
+                    continue;
+                }
+                let undo = board.play(square, cell);
+                count += perft_canplay(board, depth - 1);
+                board.unplay(undo);
+            }
+        }
+        count
+    }
+
+    /// Count the number of move sequences of the given length using ordered().
+    fn perft_ordered(board: &mut Board, depth: usize) -> usize {
+        if depth == 0 || board.isover().is_some() {
+            return 1;
+        }
+        let mut count = 0;
+        for mv in board.ordered() {
+            let undo = board.play(mv.square, mv.cell);
+            count += perft_ordered(board, depth - 1);
+            board.unplay(undo);
+        }
+        count
+    }
+
+    /// Test whether the legal moves mask gives the same moves as canplay().
+    #[test]
+    fn legal_moves_mask() {
+        for _ in 0..1000 {
+            let board = Board::random();
+            if board.isfirst() || board.isover().is_some() {
+                continue;
+            }
+
+            let square = board.square().unwrap();
+            let mask = board.legal_moves_mask();
+            for cell in 0..5 {
+                assert_eq!(mask & 1 << cell != 0, board.canplay(square, cell));
+            }
+            assert_eq!(board.ordered().count(), mask.count_ones() as usize);
+        }
+    }
+
+    /// Test whether the move generation is the same as canplay() for all the
+    /// positions reachable within a couple of moves.
+    #[test]
+    fn perft() {
+        for depth in 0..=6 {
+            assert_eq!(
+                perft_ordered(&mut Board::new(), depth),
+                perft_canplay(&mut Board::new(), depth)
+            );
+        }
+
+        // Also check some positions with full squares and takes.
+        for notation in ["00203010", "12101411", "442343214122024", "24232021122"] {
+            let mut board = Board::load(notation).unwrap();
+            assert_eq!(perft_ordered(&mut board, 5), perft_canplay(&mut board, 5));
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use super::{Board, Move, MoveError, Undo};
+use crate::error;
+
+/// A board together with the moves which led to it, such that moves can be
+/// undone and redone and the notation is always available.
+#[derive(Debug, Clone)]
+pub struct Position {
+    /// The current board.
+    board: Board,
+    /// The moves made to reach the current board.
+    history: Vec<Move>,
+    /// The information needed to take back every move in the history.
+    undos: Vec<Undo>,
+    /// The moves which have been undone, the last one is redone first.
+    undone: Vec<Move>,
+}
+
+impl Position {
+    /// Create a new position with an empty board.
+    pub fn new() -> Self {
+        Self {
+            board: Board::new(),
+            history: Vec::new(),
+            undos: Vec::new(),
+            undone: Vec::new(),
+        }
+    }
+
+    /// Create a position from the given notation, see Board::load.
+    pub fn load(notation: &str) -> error::Result<Self> {
+        // Let the board check the notation for errors.
+        Board::load(notation)?;
+
+        // The first move is the only one which includes the square.
+        let mut position = Self::new();
+        let mut digits = notation.chars().map(|c| c as u8 - b'0');
+        if let (Some(square), Some(cell)) = (digits.next(), digits.next()) {
+            position.push(Move::new(square, cell));
+        }
+        for cell in digits {
+            position.push(Move::new(position.board.square().unwrap(), cell));
+        }
+
+        Ok(position)
+    }
+
+    /// Return the current board.
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Return the moves made so far.
+    pub fn history(&self) -> &[Move] {
+        &self.history
+    }
+
+    /// Return the notation of the moves made so far, e.g. 0123. The first move
+    /// consists of the square and the cell, the other moves only of the cell.
+    pub fn notation(&self) -> String {
+        let mut notation = String::new();
+        for (index, mv) in self.history.iter().enumerate() {
+            if index == 0 {
+                notation.push_str(&mv.square.to_string());
+            }
+            notation.push_str(&mv.cell.to_string());
+        }
+        notation
+    }
+
+    /// Make the given move if it is legal. This clears the moves to redo.
+    pub fn play(&mut self, mv: Move) -> std::result::Result<(), MoveError> {
+        self.board.check(mv)?;
+        self.push(mv);
+        self.undone.clear();
+        Ok(())
+    }
+
+    /// Undo the last move and return it, if there is any.
+    pub fn undo(&mut self) -> Option<Move> {
+        let mv = self.history.pop()?;
+        self.board.unplay(self.undos.pop().unwrap());
+        self.undone.push(mv);
+        Some(mv)
+    }
+
+    /// Redo the last undone move and return it, if there is any.
+    pub fn redo(&mut self) -> Option<Move> {
+        let mv = self.undone.pop()?;
+        self.push(mv);
+        Some(mv)
+    }
+
+    /// Make the given move, which should be legal, and add it to the history.
+    fn push(&mut self, mv: Move) {
+        self.undos.push(self.board.play(mv.square, mv.cell));
+        self.history.push(mv);
+    }
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{player::Player, random};
+
+    /// Test whether the history is correctly recovered from the notation.
+    #[test]
+    fn load() {
+        let position = Position::load("0123").unwrap();
+        assert_eq!(
+            position.history(),
+            &[Move::new(0, 1), Move::new(1, 2), Move::new(2, 3)]
+        );
+        assert_eq!(position.notation(), "0123");
+        assert_eq!(position.board().movecount(), 3);
+
+        assert!(Position::load("").unwrap().history().is_empty());
+        assert!(Position::load("0").is_err());
+    }
+
+    /// Test playing, undoing and redoing moves.
+    #[test]
+    fn undo_redo() {
+        let mut position = Position::new();
+        assert_eq!(position.undo(), None);
+
+        position.play(Move::new(0, 0)).unwrap();
+        position.play(Move::new(0, 2)).unwrap();
+        assert_eq!(position.play(Move::new(1, 0)), Err(MoveError::Square(2, 1)));
+        assert_eq!(position.notation(), "002");
+
+        assert_eq!(position.undo(), Some(Move::new(0, 2)));
+        assert_eq!(position.notation(), "00");
+        assert_eq!(position.board().key(), Board::load("00").unwrap().key());
+
+        assert_eq!(position.undo(), Some(Move::new(0, 0)));
+        assert!(position.board().isfirst());
+        assert_eq!(position.notation(), "");
+
+        assert_eq!(position.redo(), Some(Move::new(0, 0)));
+        assert_eq!(position.redo(), Some(Move::new(0, 2)));
+        assert_eq!(position.redo(), None);
+        assert_eq!(position.notation(), "002");
+        assert_eq!(position.board().key(), Board::load("002").unwrap().key());
+
+        // Making a move forgets about the undone moves.
+        position.undo();
+        position.play(Move::new(0, 1)).unwrap();
+        assert_eq!(position.redo(), None);
+        assert_eq!(position.notation(), "001");
+    }
+
+    /// Test undoing all the moves of random games.
+    #[test]
+    fn undo_random() {
+        for _ in 0..100 {
+            let mut position = Position::new();
+            let mut keys = vec![position.board().key()];
+
+            while position.board().isover().is_none() {
+                let mv = random::Random.play(position.board());
+                position.play(mv).unwrap();
+                keys.push(position.board().key());
+            }
+
+            while position.undo().is_some() {
+                keys.pop();
+                assert_eq!(position.board().key(), *keys.last().unwrap());
+            }
+            assert_eq!(keys.len(), 1);
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use super::Board;
+use crate::error;
+
+/// The rules of the game. The default rules are the normal rules of katalon,
+/// other rules can be used to study variants of the game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rules {
+    /// The number of stones each player starts with.
+    stones: u8,
+    /// The number of takes in a row after which the game is a draw.
+    takestreak_limit: u8,
+    /// Whether the first move may not be made in the center square.
+    first_move_restricted: bool,
+    /// The number of squares in which the player onturn needs the majority to
+    /// win when the board is full, otherwise the other player wins.
+    full_board_squares: u8,
+}
+
+impl Rules {
+    /// Create rules with the given settings, if they are supported.
+    pub fn new(
+        stones: u8,
+        takestreak_limit: u8,
+        first_move_restricted: bool,
+        full_board_squares: u8,
+    ) -> error::Result<Self> {
+        let rules = Self {
+            stones,
+            takestreak_limit,
+            first_move_restricted,
+            full_board_squares,
+        };
+
+        // At least one square should be completable and the key has 7 bits
+        // for the takestreak.
+        if !(6..=Board::NUMBER_OF_STONES).contains(&stones) {
+            return Err(error::Error::Rules(format!(
+                "The number of stones should be between 6 and {}.",
+                Board::NUMBER_OF_STONES
+            )));
+        }
+        if !(1..=127).contains(&takestreak_limit) {
+            return Err(error::Error::Rules(String::from(
+                "The takestreak limit should be between 1 and 127.",
+            )));
+        }
+        if !(1..=5).contains(&full_board_squares) {
+            return Err(error::Error::Rules(String::from(
+                "The number of squares needed on a full board should be between 1 and 5.",
+            )));
+        }
+
+        // The evaluation can only encode distances up to Board::MOVECOUNT_LIMIT.
+        if rules.movecount_limit() > Board::MOVECOUNT_LIMIT {
+            return Err(error::Error::Rules(format!(
+                "The movecount limit of these rules {} exceeds {}, use less stones or a lower takestreak limit.",
+                rules.movecount_limit(),
+                Board::MOVECOUNT_LIMIT
+            )));
+        }
+
+        Ok(rules)
+    }
+
+    /// Return the number of stones each player starts with.
+    pub fn stones(&self) -> u8 {
+        self.stones
+    }
+
+    /// Return the number of takes in a row after which the game is a draw.
+    pub fn takestreak_limit(&self) -> u8 {
+        self.takestreak_limit
+    }
+
+    /// Return whether the first move may not be made in the center square.
+    pub fn first_move_restricted(&self) -> bool {
+        self.first_move_restricted
+    }
+
+    /// Return the number of squares in which the player onturn needs the
+    /// majority to win when the board is full.
+    pub fn full_board_squares(&self) -> u8 {
+        self.full_board_squares
+    }
+
+    /// An upperbound on the number of moves that can be made in a game with
+    /// these rules, computed in the same way as Board::MOVECOUNT_LIMIT.
+    pub fn movecount_limit(&self) -> i16 {
+        (2 * self.stones as i16 - 10) * self.takestreak_limit as i16
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            stones: Board::NUMBER_OF_STONES,
+            takestreak_limit: Board::TAKESTREAK_LIMIT,
+            first_move_restricted: false,
+            full_board_squares: 3,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{Move, MoveError, Result};
+
+    /// Test whether the default rules are the normal rules.
+    #[test]
+    fn rules_default() {
+        let rules = Rules::default();
+        assert_eq!(Rules::new(12, 15, false, 3).unwrap(), rules);
+        assert_eq!(rules.movecount_limit(), Board::MOVECOUNT_LIMIT);
+        assert_eq!(Board::new().rules(), &rules);
+    }
+
+    /// Test whether unsupported rules are refused.
+    #[test]
+    fn rules_invalid() {
+        assert!(Rules::new(5, 15, false, 3).is_err());
+        assert!(Rules::new(13, 15, false, 3).is_err());
+        assert!(Rules::new(12, 0, false, 3).is_err());
+        assert!(Rules::new(12, 16, false, 3).is_err());
+        assert!(Rules::new(12, 15, false, 0).is_err());
+        assert!(Rules::new(12, 15, false, 6).is_err());
+        assert!(Rules::new(10, 20, false, 3).is_ok());
+    }
+
+    /// Test a variant with less stones.
+    #[test]
+    fn rules_stones() {
+        let rules = Rules::new(10, 15, false, 3).unwrap();
+        let board = Board::load_with_rules("002030110144031332242", rules).unwrap();
+        assert_eq!(board.isover(), Some(Result::Player1));
+        assert_eq!(board.validate(), Ok(()));
+
+        assert_eq!(board.stones, [0, 3]);
+
+        let loaded = Board::from_key_with_rules(board.key(), rules);
+        assert_eq!(loaded.stones, board.stones);
+    }
+
+    /// Test a variant with a lower takestreak limit.
+    #[test]
+    fn rules_takestreak() {
+        let rules = Rules::new(12, 9, false, 3).unwrap();
+        let start = String::from("20033102212432011410302234201");
+        let cycle = "21103".repeat(6);
+
+        let board = Board::load_with_rules(&(start.clone() + &cycle[..6]), rules).unwrap();
+        assert_eq!(board.isover(), None);
+
+        let board = Board::load_with_rules(&(start + &cycle[..7]), rules).unwrap();
+        assert_eq!(board.isover(), Some(Result::Draw));
+    }
+
+    /// Test a variant in which the first move cannot be in the center square.
+    #[test]
+    fn rules_first_move() {
+        let rules = Rules::new(12, 15, true, 3).unwrap();
+        let mut board = Board::with_rules(rules);
+
+        assert!(!board.canplay(2, 2));
+        assert!(board.canplay(0, 4));
+        assert!(board.moves().iter().all(|mv| mv.square != 2));
+        assert_eq!(board.check(Move::new(2, 0)), Err(MoveError::Restricted));
+        assert!(Board::load_with_rules("22", rules).is_err());
+
+        assert_eq!(board.try_play(Move::new(0, 2)), Ok(()));
+        assert!(board.canplay(2, 2));
+    }
+
+    /// Test a variant with another number of squares needed on a full board.
+    #[test]
+    fn rules_full_board() {
+        let moves = "200301314022334323344241120010";
+        assert_eq!(Board::load(moves).unwrap().isover(), Some(Result::Player2));
+
+        let rules = Rules::new(12, 15, false, 5).unwrap();
+        let board = Board::load_with_rules(moves, rules).unwrap();
+        assert_eq!(board.isover(), Some(Result::Player1));
+    }
+}
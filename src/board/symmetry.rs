@@ -0,0 +1,146 @@
+use super::Board;
+
+/// A symmetry of the board, see misc/symmetries.txt for the mappings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Symmetry {
+    /// Leave the board as it is.
+    Identity,
+    /// Flip over the diagonal through square 1 and 3.
+    FlipDiagonal13,
+    /// Flip over the diagonal through square 0 and 4.
+    FlipDiagonal04,
+    /// Flip over the horizontal axis.
+    FlipHorizontal,
+    /// Flip over the vertical axis.
+    FlipVertical,
+    /// Rotate 90 degrees.
+    Rotation90,
+    /// Rotate 180 degrees.
+    Rotation180,
+    /// Rotate 270 degrees.
+    Rotation270,
+}
+
+impl Symmetry {
+    /// All the symmetries, in the same order as the keys of Board::keys().
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::Identity,
+        Symmetry::FlipDiagonal13,
+        Symmetry::FlipDiagonal04,
+        Symmetry::FlipHorizontal,
+        Symmetry::FlipVertical,
+        Symmetry::Rotation90,
+        Symmetry::Rotation180,
+        Symmetry::Rotation270,
+    ];
+
+    /// Return the index of this symmetry in Symmetry::ALL.
+    pub(super) fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Return the symmetry which undoes this symmetry.
+    pub fn inverse(self) -> Self {
+        match self {
+            Symmetry::Rotation90 => Symmetry::Rotation270,
+            Symmetry::Rotation270 => Symmetry::Rotation90,
+            symmetry => symmetry, // flips and rotation 180 are their own inverse
+        }
+    }
+
+    /// Map the given (square, cell) to its place on the transformed board.
+    pub fn map(self, square: u8, cell: u8) -> (u8, u8) {
+        debug_assert!(square < 5 && cell < 5);
+
+        match self {
+            Symmetry::Identity => (square, cell),
+            symmetry => {
+                let index = Board::SYMMETRIES[symmetry.index() - 1][(square * 5 + cell) as usize];
+                (index as u8 / 5, index as u8 % 5)
+            }
+        }
+    }
+
+    /// Map the given (square, cell) on the transformed board back to the original board.
+    pub fn unmap(self, square: u8, cell: u8) -> (u8, u8) {
+        self.inverse().map(square, cell)
+    }
+}
+
+impl Board {
+    /// Return the board transformed by the given symmetry. Unlike Board::from_key
+    /// the lastmove, stones and movecount are fully preserved.
+    pub fn transform(&self, symmetry: Symmetry) -> Self {
+        let mut board = self.clone();
+
+        if symmetry == Symmetry::Identity {
+            return board;
+        }
+
+        let index = symmetry.index() - 1;
+        board.state = Self::symmetry_map(self.state, index) as u32;
+        board.mask = Self::symmetry_map(self.mask, index) as u32;
+        board.lastmove = self
+            .lastmove
+            .map(|(square, cell)| symmetry.map(square, cell));
+        board.rehash();
+
+        board
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test whether a symmetry followed by its inverse gives back the original.
+    #[test]
+    fn inverse() {
+        for symmetry in Symmetry::ALL {
+            for square in 0..5 {
+                for cell in 0..5 {
+                    let (s, c) = symmetry.map(square, cell);
+                    assert_eq!(symmetry.unmap(s, c), (square, cell));
+                }
+            }
+        }
+    }
+
+    /// Test transforming a board and transforming it back.
+    #[test]
+    fn transform_inverse() {
+        for _ in 0..1000 {
+            let board = Board::random();
+            for symmetry in Symmetry::ALL {
+                let transformed = board.transform(symmetry);
+                assert_eq!(transformed.transform(symmetry.inverse()).key(), board.key());
+                assert_eq!(transformed.movecount(), board.movecount());
+            }
+        }
+    }
+
+    /// Test whether the transformed boards correspond to the symmetrical keys.
+    #[test]
+    fn transform_keys() {
+        for _ in 0..1000 {
+            let board = Board::random();
+            let keys: Vec<u64> = board.keys().collect();
+            for symmetry in Symmetry::ALL {
+                let transformed = board.transform(symmetry);
+                assert_eq!(transformed.key(), keys[symmetry.index()]);
+                assert_eq!(transformed.canonical_key(), board.canonical_key());
+            }
+        }
+    }
+
+    /// Test whether the lastmove is mapped along with the board.
+    #[test]
+    fn transform_lastmove() {
+        let board = Board::load("21").unwrap();
+        let transformed = board.transform(Symmetry::Rotation90);
+
+        assert_eq!(transformed.lastmove, Some((2, 0)));
+        assert_eq!(transformed.square(), Some(0));
+        assert_eq!(transformed.stones, board.stones);
+    }
+}
@@ -0,0 +1,225 @@
+use super::Board;
+use crate::player;
+
+/// The reason why a board is not a valid position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Invalid {
+    /// There are stones outside of the 25 cells, or stones of the player
+    /// onturn on cells which are empty according to the mask.
+    Cells,
+    /// The two parts of the given double cell (square, cell) differ.
+    Double(u8, u8),
+    /// The number of stones left plus the number of stones placed of the given
+    /// player does not add up to the number of stones a player starts with.
+    Stones(player::Players),
+    /// The player onturn does not match the movecount.
+    Onturn,
+    /// The lastmove is missing, out of range or does not point to a stone of
+    /// the player who just moved.
+    Lastmove,
+    /// The takestreak exceeds the limit or the number of moves made.
+    Takestreak,
+    /// The movecount is negative, exceeds the limit or is too low for the
+    /// number of stones on the board.
+    Movecount,
+    /// The given square is completed although the game should have ended
+    /// before this position was reached.
+    Square(u8),
+}
+
+impl std::fmt::Display for Invalid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Invalid::Cells => write!(f, "the stones do not fit on the board"),
+            Invalid::Double(square, cell) => {
+                write!(f, "the double cell ({}, {}) is inconsistent", square, cell)
+            }
+            Invalid::Stones(player) => write!(f, "the stone count of {} is wrong", player),
+            Invalid::Onturn => write!(f, "the player onturn does not match the movecount"),
+            Invalid::Lastmove => write!(f, "the lastmove is invalid"),
+            Invalid::Takestreak => write!(f, "the takestreak is invalid"),
+            Invalid::Movecount => write!(f, "the movecount is invalid"),
+            Invalid::Square(square) => {
+                write!(f, "square {} should have ended the game already", square)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Invalid {}
+
+impl Board {
+    /// Check whether this board is a position which can be reached in a game.
+    /// Use this on boards from untrusted sources before searching them, as the
+    /// board functions only check their input with debug assertions.
+    pub fn validate(&self) -> std::result::Result<(), Invalid> {
+        let board: u32 = 0b11111_11111_11111_11111_11111;
+
+        // All the stones should be on the board.
+        if self.mask & !board != 0 || self.state & !self.mask != 0 {
+            return Err(Invalid::Cells);
+        }
+
+        // Both parts of a double cell should be the same.
+        for square in 0..5 {
+            for cell in 0..5 {
+                if let Some((s, c)) = Self::double(square, cell) {
+                    let bit = 1 << (square * 5 + cell);
+                    let double = 1 << (s * 5 + c);
+                    if (self.mask & bit == 0) != (self.mask & double == 0)
+                        || (self.state & bit == 0) != (self.state & double == 0)
+                    {
+                        return Err(Invalid::Double(square, cell));
+                    }
+                }
+            }
+        }
+
+        // Every stone is either on the board or still in the hands of the player.
+        let placed_onturn = Self::placed(self.state);
+        let placed_other = Self::placed(self.state ^ self.mask);
+        let stones = self.rules.stones();
+        if placed_onturn + self.stones[self.onturn as usize] != stones {
+            return Err(Invalid::Stones(self.onturn));
+        }
+        if placed_other + self.stones[self.onturn.other() as usize] != stones {
+            return Err(Invalid::Stones(self.onturn.other()));
+        }
+
+        // Every move places exactly one stone, taking replaces a stone.
+        let placed = (placed_onturn + placed_other) as i16;
+        if self.movecount < placed || self.movecount > self.rules.movecount_limit() {
+            return Err(Invalid::Movecount);
+        }
+
+        // Player1 always moves on an even movecount.
+        if (self.movecount % 2 == 0) != (self.onturn == player::Players::Player1) {
+            return Err(Invalid::Onturn);
+        }
+
+        if self.takestreak > self.rules.takestreak_limit()
+            || self.takestreak as i16 > self.movecount
+        {
+            return Err(Invalid::Takestreak);
+        }
+
+        // The lastmove should be a stone of the player who just moved.
+        let (square, cell) = match self.lastmove {
+            None if self.movecount == 0 => return Ok(()),
+            None => return Err(Invalid::Lastmove),
+            Some(_) if self.movecount == 0 => return Err(Invalid::Lastmove),
+            Some((square, cell)) if square >= 5 || cell >= 5 => return Err(Invalid::Lastmove),
+            Some(lastmove) => lastmove,
+        };
+        let bit = 1 << (square * 5 + cell);
+        if self.mask & bit == 0 || self.state & bit != 0 {
+            return Err(Invalid::Lastmove);
+        }
+
+        // Only the lastmove can have completed a square, otherwise the game
+        // would already have ended before.
+        let double = Self::double(square, cell).map(|(s, _)| s);
+        for s in 0..5 {
+            let mask_square = 0b11111 << (s * 5);
+            if self.mask & mask_square != mask_square {
+                continue;
+            }
+
+            let onturn = self.state & mask_square == mask_square;
+            let other = self.state & mask_square == 0;
+            if onturn || (other && s != square && Some(s) != double) {
+                return Err(Invalid::Square(s));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test whether all the boards reached by playing are valid.
+    #[test]
+    fn validate_random() {
+        assert_eq!(Board::new().validate(), Ok(()));
+        for _ in 0..1000 {
+            assert_eq!(Board::random().validate(), Ok(()));
+        }
+    }
+
+    /// Test the detection of corrupted cells.
+    #[test]
+    fn validate_cells() {
+        let mut board1 = Board::load("0020").unwrap();
+        board1.mask |= 1 << 25;
+        assert_eq!(board1.validate(), Err(Invalid::Cells));
+
+        let mut board2 = Board::load("0020").unwrap();
+        board2.state |= 1 << 24;
+        assert_eq!(board2.validate(), Err(Invalid::Cells));
+
+        let mut board3 = Board::load("04").unwrap();
+        board3.mask ^= 1 << 10;
+        assert_eq!(board3.validate(), Err(Invalid::Double(0, 4)));
+    }
+
+    /// Test the detection of wrong stone counts.
+    #[test]
+    fn validate_stones() {
+        let mut board = Board::load("0020").unwrap();
+        board.stones[0] += 1;
+        assert_eq!(
+            board.validate(),
+            Err(Invalid::Stones(player::Players::Player1))
+        );
+    }
+
+    /// Test the detection of an inconsistent movecount, onturn and takestreak.
+    #[test]
+    fn validate_counts() {
+        let mut board1 = Board::load("0020").unwrap();
+        board1.movecount = 2;
+        assert_eq!(board1.validate(), Err(Invalid::Movecount));
+
+        let mut board2 = Board::load("0020").unwrap();
+        board2.movecount = 4;
+        assert_eq!(board2.validate(), Err(Invalid::Onturn));
+
+        let mut board3 = Board::load("00203010").unwrap();
+        board3.takestreak = Board::TAKESTREAK_LIMIT + 1;
+        assert_eq!(board3.validate(), Err(Invalid::Takestreak));
+    }
+
+    /// Test the detection of an invalid lastmove.
+    #[test]
+    fn validate_lastmove() {
+        let mut board1 = Board::load("0020").unwrap();
+        board1.lastmove = None;
+        assert_eq!(board1.validate(), Err(Invalid::Lastmove));
+
+        let mut board2 = Board::load("0020").unwrap();
+        board2.lastmove = Some((0, 3));
+        assert_eq!(board2.validate(), Err(Invalid::Lastmove));
+
+        let mut board3 = Board::load("0020").unwrap();
+        board3.lastmove = Some((0, 2));
+        assert_eq!(board3.validate(), Err(Invalid::Lastmove));
+    }
+
+    /// Test the detection of squares which should have ended the game.
+    #[test]
+    fn validate_square() {
+        // The lastmove completed the square.
+        let mut board = Board::load("2320212422").unwrap();
+        assert_eq!(board.validate(), Ok(()));
+
+        // Some other move completed the square.
+        board.lastmove = Some((4, 3));
+        board.mask |= 1 << 23;
+        board.stones[0] -= 1;
+        board.movecount += 2;
+        assert_eq!(board.validate(), Err(Invalid::Square(2)));
+    }
+}
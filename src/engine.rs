@@ -0,0 +1,283 @@
+use crate::{board, error, eval, solver, stats::search};
+
+// The engine protocol is line based, the engine reads commands from stdin and
+// answers on stdout, similar to UCI in chess:
+//
+//   > katalon                    identify, answered by id lines and katalonok
+//   > isready                    answered by readyok
+//   > newgame                    start from the empty board
+//   > position [startpos | fen <fen>] [moves <move>...]
+//   > go [movetime <ms>] [nodes <n>] [depth <n>] [infinite]
+//   < info time <ms> nodes <n> nps <n> window <min> <max> ...
+//   < info eval <result> <distance> pv <move>... nodes <n> time <ms>
+//   < bestmove <move>
+//   > stop                       stop the search, answered by bestmove
+//   > quit
+//
+// Positions are always played with the default rules, neither the moves nor
+// the fen say anything about the rules.
+
+/// A command sent to the engine.
+#[derive(Debug, Clone)]
+pub enum Command {
+    /// Ask the engine to identify itself.
+    Katalon,
+    /// Ask whether the engine is ready for the next command.
+    IsReady,
+    /// Start a new game from the empty board.
+    NewGame,
+    /// Set up the position to search.
+    Position(board::Board),
+    /// Search the position within the limits and answer with the best move.
+    Go(solver::SearchLimits),
+    /// Stop the search as soon as possible.
+    Stop,
+    /// Stop the engine.
+    Quit,
+}
+
+impl Command {
+    /// Parse a line of the engine protocol.
+    pub fn parse(line: &str) -> error::Result<Self> {
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("katalon") => Ok(Command::Katalon),
+            Some("isready") => Ok(Command::IsReady),
+            Some("newgame") => Ok(Command::NewGame),
+            Some("position") => parse_position(words.collect()),
+            Some("go") => parse_go(words.collect()),
+            Some("stop") => Ok(Command::Stop),
+            Some("quit") => Ok(Command::Quit),
+            Some(command) => Err(error::Error::Format(format!(
+                "Unknown command {}.",
+                command
+            ))),
+            None => Err(error::Error::Format(String::from("Empty command."))),
+        }
+    }
+}
+
+impl std::fmt::Display for Command {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Command::Katalon => write!(f, "katalon"),
+            Command::IsReady => write!(f, "isready"),
+            Command::NewGame => write!(f, "newgame"),
+            Command::Position(board) => write!(f, "position fen {}", board.to_fen()),
+            Command::Go(limits) => {
+                write!(f, "go")?;
+                if let Some(time) = limits.time {
+                    write!(f, " movetime {}", time.as_millis())?;
+                }
+                if let Some(nodes) = limits.nodes {
+                    write!(f, " nodes {}", nodes)?;
+                }
+                if let Some(depth) = limits.depth {
+                    write!(f, " depth {}", depth)?;
+                }
+                Ok(())
+            }
+            Command::Stop => write!(f, "stop"),
+            Command::Quit => write!(f, "quit"),
+        }
+    }
+}
+
+/// Parse the arguments of a position command.
+fn parse_position(words: Vec<&str>) -> error::Result<Command> {
+    let split = words.iter().position(|&word| word == "moves");
+    let (setup, moves) = match split {
+        Some(index) => (&words[..index], &words[index + 1..]),
+        None => (&words[..], &[][..]),
+    };
+
+    let mut board = match setup {
+        [] | ["startpos"] => board::Board::new(),
+        ["fen", fen @ ..] => board::Board::from_fen(&fen.join(" "))?,
+        _ => {
+            return Err(error::Error::Format(String::from(
+                "Please use: position [startpos | fen <fen>] [moves <move>...].",
+            )))
+        }
+    };
+
+    // The square of a move may be left out if it follows from the last move.
+    for text in moves {
+        let mv = board::Move::parse(&board, text)?;
+        board.try_play(mv)?;
+    }
+
+    Ok(Command::Position(board))
+}
+
+/// Parse the arguments of a go command.
+fn parse_go(words: Vec<&str>) -> error::Result<Command> {
+    let mut limits = solver::SearchLimits::new();
+    let mut words = words.into_iter();
+
+    while let Some(word) = words.next() {
+        if word == "infinite" {
+            continue;
+        }
+
+        let value = words.next().and_then(|value| value.parse::<u64>().ok());
+        limits = match (word, value) {
+            ("movetime", Some(ms)) => limits.time(std::time::Duration::from_millis(ms)),
+            ("nodes", Some(nodes)) => limits.nodes(nodes as usize),
+            ("depth", Some(depth)) if depth <= i16::MAX as u64 => limits.depth(depth as i16),
+            _ => {
+                return Err(error::Error::Format(String::from(
+                    "Please use: go [movetime <ms>] [nodes <n>] [depth <n>] [infinite].",
+                )))
+            }
+        };
+    }
+
+    Ok(Command::Go(limits))
+}
+
+/// Format a message as an info line, messages with multiple lines are joined.
+pub fn info_string(message: impl std::fmt::Display) -> String {
+    let message = message.to_string();
+    format!(
+        "info string {}",
+        message.lines().collect::<Vec<_>>().join(" ")
+    )
+}
+
+/// Format the progress of a running search as an info line.
+pub fn info_progress(progress: &search::Progress) -> String {
+    format!(
+        "info time {} nodes {} nps {} window {} {} nullwindows {} hashfull {}",
+        progress.elapsed.as_millis(),
+        progress.visited,
+        progress.visited_per_second(),
+        progress.min.raw(),
+        progress.max.raw(),
+        progress.nullwindows,
+        (progress.fill() * 1000.0) as usize,
+    )
+}
+
+/// Format the result of a finished search as an info line.
+pub fn info_result(value: eval::Eval, pv: &[board::Move], stats: &search::Stats) -> String {
+    let (result, distance) = value.human();
+    let pv: Vec<String> = pv.iter().map(|mv| mv.to_string()).collect();
+    format!(
+        "info eval {} {} pv {} nodes {} time {}",
+        result,
+        distance,
+        pv.join(" "),
+        stats.visited,
+        stats.time.as_millis(),
+    )
+}
+
+/// Parse the move of a bestmove line, if the line is one.
+pub fn parse_bestmove(line: &str) -> Option<error::Result<board::Move>> {
+    match line.split_whitespace().collect::<Vec<_>>()[..] {
+        ["bestmove", mv] => Some(mv.parse().map_err(error::Error::from)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test whether positions are set up from the start or a fen.
+    #[test]
+    fn position() {
+        let expected = board::Board::load("0123").unwrap();
+        for line in [
+            "position moves 01 12 23",
+            "position startpos moves 01 2 3",
+            "position fen ...../...../...../...../..... X 12,12 - 0 0 moves 01 12 23",
+        ] {
+            match Command::parse(line) {
+                Ok(Command::Position(board)) => assert_eq!(board.key(), expected.key(), "{}", line),
+                other => panic!("{}: {:?}", line, other),
+            }
+        }
+
+        assert!(matches!(
+            Command::parse("position"),
+            Ok(Command::Position(_))
+        ));
+        assert!(Command::parse("position moves 012").is_err());
+        assert!(Command::parse("position moves 1").is_err());
+        assert!(Command::parse("position moves 01 23").is_err());
+        assert!(Command::parse("position somewhere").is_err());
+    }
+
+    /// Test whether the limits of a search are read.
+    #[test]
+    fn go() {
+        match Command::parse("go movetime 1500 nodes 1000 depth 12") {
+            Ok(Command::Go(limits)) => {
+                assert_eq!(limits.time, Some(std::time::Duration::from_millis(1500)));
+                assert_eq!(limits.nodes, Some(1000));
+                assert_eq!(limits.depth, Some(12));
+            }
+            other => panic!("{:?}", other),
+        }
+
+        match Command::parse("go infinite") {
+            Ok(Command::Go(limits)) => assert!(limits.time.is_none() && limits.nodes.is_none()),
+            other => panic!("{:?}", other),
+        }
+
+        assert!(Command::parse("go movetime").is_err());
+        assert!(Command::parse("go nodes many").is_err());
+        assert!(Command::parse("go fast").is_err());
+    }
+
+    /// Test whether the commands can be written and read again.
+    #[test]
+    fn roundtrip() {
+        let board = board::Board::load("221400203101122").unwrap();
+        let limits = solver::SearchLimits::new()
+            .time(std::time::Duration::from_millis(250))
+            .depth(4);
+
+        let line = Command::Position(board.clone()).to_string();
+        match Command::parse(&line) {
+            Ok(Command::Position(parsed)) => assert_eq!(parsed.to_fen(), board.to_fen()),
+            other => panic!("{:?}", other),
+        }
+
+        let line = Command::Go(limits).to_string();
+        assert_eq!(line, "go movetime 250 depth 4");
+
+        for command in [
+            Command::Katalon,
+            Command::IsReady,
+            Command::Stop,
+            Command::Quit,
+        ] {
+            let line = command.to_string();
+            assert_eq!(Command::parse(&line).unwrap().to_string(), line);
+        }
+        assert!(Command::parse("").is_err());
+        assert!(Command::parse("jump").is_err());
+    }
+
+    /// Test whether the best move is read from the answer of an engine.
+    #[test]
+    fn bestmove() {
+        assert_eq!(
+            parse_bestmove("bestmove 23").unwrap().unwrap(),
+            board::Move::new(2, 3)
+        );
+        assert!(parse_bestmove("bestmove none").unwrap().is_err());
+        assert!(parse_bestmove("info eval win 3").is_none());
+    }
+
+    /// Test whether info strings stay on a single line.
+    #[test]
+    fn info() {
+        let error = error::Error::from(board::MoveError::Square(2, 1));
+        assert_eq!(info_string(&error).lines().count(), 1);
+        assert!(info_string(&error).starts_with("info string "));
+    }
+}
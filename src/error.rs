@@ -0,0 +1,119 @@
+use crate::board;
+
+/// The errors which can be returned by the library.
+#[derive(Debug)]
+pub enum Error {
+    /// The notation of the moves or of the position could not be read.
+    Notation(String),
+    /// The move cannot be made on the board.
+    Move(board::MoveError),
+    /// The position is not a position which can be reached in a game.
+    Position(board::Invalid),
+    /// The rules are not supported.
+    Rules(String),
+    /// The search did not finish before the timeout.
+    Timeout,
+    /// The search was cancelled before it finished.
+    Cancelled,
+    /// The search reached its node limit before it finished.
+    NodeLimit,
+    /// The search reached its depth limit before it finished.
+    DepthLimit,
+    /// Reading or writing failed.
+    Io(std::io::Error),
+    /// Some data does not have the expected format.
+    Format(String),
+}
+
+/// A result with the error type of this library.
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Notation(message) => write!(f, "{}", message),
+            Error::Move(error) => write!(f, "{}", error),
+            Error::Position(invalid) => write!(f, "The position is invalid: {}.", invalid),
+            Error::Rules(message) => write!(f, "{}", message),
+            Error::Timeout => write!(f, "The search did not finish before the timeout."),
+            Error::Cancelled => write!(f, "The search was cancelled."),
+            Error::NodeLimit => write!(f, "The search reached its node limit."),
+            Error::DepthLimit => write!(f, "The search reached its depth limit."),
+            Error::Io(error) => write!(f, "{}", error),
+            Error::Format(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Move(error) => Some(error),
+            Error::Position(invalid) => Some(invalid),
+            Error::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<board::MoveError> for Error {
+    fn from(error: board::MoveError) -> Self {
+        Error::Move(error)
+    }
+}
+
+impl From<board::Invalid> for Error {
+    fn from(invalid: board::Invalid) -> Self {
+        Error::Position(invalid)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error as _;
+
+    /// Test whether the errors of the board are wrapped with their source.
+    #[test]
+    fn from_board() {
+        let error = Error::from(board::MoveError::OwnStone);
+        assert!(matches!(error, Error::Move(board::MoveError::OwnStone)));
+        assert!(error.source().is_some());
+        assert_eq!(error.to_string(), board::MoveError::OwnStone.to_string());
+
+        let error = Error::from(board::Invalid::Onturn);
+        assert!(matches!(error, Error::Position(board::Invalid::Onturn)));
+        assert!(error.source().is_some());
+    }
+
+    /// Test whether the kind of error can be matched on instead of the message.
+    #[test]
+    fn kinds() {
+        assert!(matches!(board::Board::load("5"), Err(Error::Notation(_))));
+        assert!(matches!(
+            board::Board::load("000"),
+            Err(Error::Move(board::MoveError::SquareNotFull))
+        ));
+        assert!(matches!(
+            board::Board::load("23202124220"),
+            Err(Error::Move(board::MoveError::GameOver))
+        ));
+        assert!(matches!(
+            board::Board::from_fen("....X/...../X..../...../..... O 11,11 04 0 1"),
+            Err(Error::Position(board::Invalid::Stones(_)))
+        ));
+        assert!(matches!(
+            board::Rules::new(12, 0, false, 3),
+            Err(Error::Rules(_))
+        ));
+
+        let error = std::fs::read("does/not/exist").unwrap_err();
+        assert!(matches!(Error::from(error), Error::Io(_)));
+    }
+}
@@ -1,6 +1,11 @@
 use crate::board;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum Result {
     Loss,
     Draw,
@@ -25,7 +30,7 @@ impl std::fmt::Display for Result {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Eval {
     /// The internal representation of the evaluation is as shown in the diagram below.
-    /// L = Loss, D = Draw, W = Win, ML = MOVECOUNT_LIMIT
+    /// L = Loss, D = Draw, W = Win, ML = MOVECOUNT_LIMIT (the highest limit of all rules)
     /// <- MIN i16                                 0                                 MAX i16 ->
     /// <------------------------------------------------------------------------------------->
     ///    |        LOSS        | |              DRAW               | |         WIN        |
@@ -66,6 +71,17 @@ impl Eval {
         })
     }
 
+    /// Create an evaluation from the given result and distance, or None if
+    /// the distance is out of range for the result, e.g. when it is read.
+    pub fn try_from_human(result: Result, distance: i16) -> Option<Self> {
+        let limit = board::Board::MOVECOUNT_LIMIT;
+        let lowest = if result == Result::Draw { -limit } else { 0 };
+        if distance < lowest || distance > limit {
+            return None;
+        }
+        Some(Self::from(result, distance))
+    }
+
     /// Give the result of the evaluation.
     pub fn result(&self) -> Result {
         if self.n > board::Board::MOVECOUNT_LIMIT {
@@ -140,6 +156,41 @@ impl std::fmt::Display for Eval {
     }
 }
 
+/// An evaluation is serialized as its result and distance, as the internal
+/// representation depends on the movecount limit.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Human {
+    result: Result,
+    distance: i16,
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Eval {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        let (result, distance) = self.human();
+        Human { result, distance }.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Eval {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let Human { result, distance } = Human::deserialize(deserializer)?;
+        Eval::try_from_human(result, distance).ok_or_else(|| {
+            serde::de::Error::custom(format!(
+                "The distance {} of a {} is out of range.",
+                distance, result
+            ))
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -161,6 +212,24 @@ mod tests {
         assert_eq!(Eval::from(Result::Win, 0).n, 2 * ML + 1);
     }
 
+    /// Test whether only distances in range are accepted.
+    #[test]
+    fn from_human() {
+        const ML: i16 = board::Board::MOVECOUNT_LIMIT;
+
+        assert_eq!(
+            Eval::try_from_human(Result::Win, 3),
+            Some(Eval::from(Result::Win, 3))
+        );
+        assert_eq!(
+            Eval::try_from_human(Result::Draw, -ML),
+            Some(Eval::from(Result::Draw, -ML))
+        );
+        assert_eq!(Eval::try_from_human(Result::Loss, -1), None);
+        assert_eq!(Eval::try_from_human(Result::Win, ML + 1), None);
+        assert_eq!(Eval::try_from_human(Result::Draw, -ML - 1), None);
+    }
+
     #[test]
     fn negate() {
         assert_eq!(-Eval::MAX, Eval::MIN);
@@ -279,4 +348,28 @@ mod tests {
         assert_eq!(format!("{}", Eval::new(-5)), "draw in -5");
         assert_eq!(format!("{}", Eval::from(Result::Win, 69)), "win in 69");
     }
+
+    /// Test whether evaluations are serialized as result and distance.
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        let value = Eval::from(Result::Win, 3);
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(json, "{\"result\":\"win\",\"distance\":3}");
+
+        for value in [
+            Eval::MIN,
+            Eval::MAX,
+            Eval::from(Result::Loss, 7),
+            Eval::from(Result::Draw, -4),
+            Eval::from(Result::Draw, 0),
+        ] {
+            let json = serde_json::to_string(&value).unwrap();
+            assert_eq!(serde_json::from_str::<Eval>(&json).unwrap(), value);
+        }
+
+        assert_eq!(serde_json::to_string(&Result::Draw).unwrap(), "\"draw\"");
+        assert!(serde_json::from_str::<Eval>("{\"result\":\"win\",\"distance\":-1}").is_err());
+        assert!(serde_json::from_str::<Eval>("{\"result\":\"won\",\"distance\":1}").is_err());
+    }
 }
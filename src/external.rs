@@ -0,0 +1,298 @@
+use crate::{board, engine, error, player, solver};
+use std::io::{BufRead, Write};
+
+/// A player directed by an external engine process speaking the engine
+/// protocol, see the engine module. Used to play different builds or
+/// different engines against each other.
+pub struct External {
+    process: std::process::Child,
+    /// The input of the engine, wrapped as play() only borrows the player.
+    stdin: std::cell::RefCell<std::process::ChildStdin>,
+    /// The lines written by the engine, read on a separate thread.
+    lines: std::sync::mpsc::Receiver<String>,
+    /// The time the engine may think about a move.
+    movetime: std::time::Duration,
+    /// The extra time given to the engine to answer.
+    grace: std::time::Duration,
+    /// The name the engine identified itself with, or else the program.
+    name: String,
+    /// Whether the engine did not answer a search at all, so its answers
+    /// cannot be told apart from the answers to that search anymore.
+    lost: std::cell::Cell<bool>,
+}
+
+impl External {
+    /// The extra time given to the engine to answer by default, which also
+    /// covers starting the engine and allocating its table.
+    pub const GRACE: std::time::Duration = std::time::Duration::from_secs(5);
+
+    /// Start the engine with the given arguments, which may think the given
+    /// time about every move.
+    pub fn spawn(
+        program: &str,
+        args: &[&str],
+        movetime: std::time::Duration,
+    ) -> error::Result<Self> {
+        Self::with_grace(program, args, movetime, Self::GRACE)
+    }
+
+    /// Start the engine, which has to answer within the grace time after the
+    /// time it may think about a move.
+    pub fn with_grace(
+        program: &str,
+        args: &[&str],
+        movetime: std::time::Duration,
+        grace: std::time::Duration,
+    ) -> error::Result<Self> {
+        let mut process = std::process::Command::new(program)
+            .args(args)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::null())
+            .spawn()?;
+
+        let stdin = process.stdin.take().expect("The input is piped.");
+        let stdout = process.stdout.take().expect("The output is piped.");
+
+        // The channel disconnects when the engine closes its output.
+        let (send_line, lines) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            for line in std::io::BufReader::new(stdout).lines() {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(_) => break,
+                };
+                if send_line.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut external = Self {
+            process,
+            stdin: std::cell::RefCell::new(stdin),
+            lines,
+            movetime,
+            grace,
+            name: String::from(program),
+            lost: std::cell::Cell::new(false),
+        };
+
+        // The engine may identify itself before it is ok.
+        let name = std::cell::RefCell::new(None);
+        external.send(&engine::Command::Katalon)?;
+        external.expect(grace, |line| {
+            if let Some(id) = line.strip_prefix("id name ") {
+                name.replace(Some(id.trim().to_string()));
+            }
+            (line == "katalonok").then_some(Ok(()))
+        })?;
+        if let Some(name) = name.into_inner() {
+            external.name = name;
+        }
+        Ok(external)
+    }
+
+    /// Send a command to the engine.
+    fn send(&self, command: &engine::Command) -> error::Result<()> {
+        let mut stdin = self.stdin.borrow_mut();
+        writeln!(stdin, "{}", command)?;
+        stdin.flush()?;
+        Ok(())
+    }
+
+    /// Read lines until the given function recognizes one, within the timeout.
+    fn expect<T>(
+        &self,
+        timeout: std::time::Duration,
+        recognize: impl Fn(&str) -> Option<error::Result<T>>,
+    ) -> error::Result<T> {
+        let deadline = std::time::Instant::now() + timeout;
+        loop {
+            let left = deadline.saturating_duration_since(std::time::Instant::now());
+            match self.lines.recv_timeout(left) {
+                Ok(line) => {
+                    if let Some(result) = recognize(&line) {
+                        return result;
+                    }
+                }
+                Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
+                    return Err(error::Error::Timeout)
+                }
+                Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
+                    return Err(error::Error::Io(std::io::Error::new(
+                        std::io::ErrorKind::UnexpectedEof,
+                        "The engine stopped.",
+                    )))
+                }
+            }
+        }
+    }
+}
+
+impl player::Player for External {
+    /// Panics if the engine fails, use try_play() to handle this.
+    fn play(&self, board: &board::Board) -> board::Move {
+        self.try_play(board)
+            .expect("The engine failed to make a move.")
+    }
+
+    /// Ask the engine for a move, which fails if the engine crashes, does not
+    /// answer in time or answers with a move which cannot be made.
+    fn try_play(&self, board: &board::Board) -> error::Result<board::Move> {
+        if self.lost.get() {
+            return Err(error::Error::Io(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                "The engine did not answer an earlier search.",
+            )));
+        }
+
+        self.send(&engine::Command::Position(board.clone()))?;
+        self.send(&engine::Command::Go(
+            solver::SearchLimits::new().time(self.movetime),
+        ))?;
+
+        let mv = match self.expect(self.movetime + self.grace, engine::parse_bestmove) {
+            Err(error::Error::Timeout) => {
+                // A late answer would be taken as the answer to the next
+                // search, so it is read and thrown away now.
+                self.send(&engine::Command::Stop).ok();
+                let late = self.expect(self.grace, |line| {
+                    engine::parse_bestmove(line).map(|_| Ok(()))
+                });
+                self.lost.set(late.is_err());
+                return Err(error::Error::Timeout);
+            }
+            result => result?,
+        };
+        board.check(mv)?;
+        Ok(mv)
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+impl Drop for External {
+    fn drop(&mut self) {
+        // Give the engine the grace time to quit by itself before killing it,
+        // it may also be gone already.
+        self.send(&engine::Command::Quit).ok();
+        self.expect(self.grace, |_| None::<error::Result<()>>).ok();
+        self.process.kill().ok();
+        self.process.wait().ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::Player;
+    use crate::{game, random};
+
+    /// Start a fake engine written as a shell script.
+    fn fake(script: &str) -> error::Result<External> {
+        External::with_grace(
+            "sh",
+            &["-c", script],
+            std::time::Duration::from_millis(10),
+            std::time::Duration::from_millis(500),
+        )
+    }
+
+    /// A fake engine which always answers with the same move.
+    const ANSWER: &str = r#"while read line; do
+        case "$line" in
+            katalon) echo "id name fake"; echo katalonok;;
+            go*) echo "info string thinking"; echo "bestmove 22";;
+            quit) exit 0;;
+        esac
+    done"#;
+
+    /// Test whether the moves of the engine are relayed and checked.
+    #[test]
+    fn moves() {
+        let engine = fake(ANSWER).unwrap();
+        assert_eq!(engine.name(), "fake");
+        assert_eq!(
+            engine.try_play(&board::Board::new()).unwrap(),
+            board::Move::new(2, 2)
+        );
+
+        let board = board::Board::load("0123").unwrap();
+        assert!(matches!(
+            engine.try_play(&board),
+            Err(error::Error::Move(board::MoveError::Square(3, 2)))
+        ));
+    }
+
+    /// Test whether engines which crash or do not answer fail.
+    #[test]
+    fn failures() {
+        assert!(matches!(fake("exit 1"), Err(error::Error::Io(_))));
+        assert!(matches!(fake("sleep 5"), Err(error::Error::Timeout)));
+        assert!(External::spawn("does/not/exist", &[], std::time::Duration::ZERO).is_err());
+
+        let silent = "read line; echo katalonok; sleep 5";
+        let engine = fake(silent).unwrap();
+        assert!(matches!(
+            engine.try_play(&board::Board::new()),
+            Err(error::Error::Timeout)
+        ));
+        assert!(matches!(
+            engine.try_play(&board::Board::new()),
+            Err(error::Error::Io(_))
+        ));
+
+        let crash = "read line; echo katalonok; read line; exit 1";
+        let engine = fake(crash).unwrap();
+        assert!(matches!(
+            engine.try_play(&board::Board::new()),
+            Err(error::Error::Io(_))
+        ));
+    }
+
+    /// Test whether a late answer is not taken as the answer to the next search.
+    #[test]
+    fn late() {
+        let late = r#"searches=0
+        while read line; do
+            case "$line" in
+                katalon) echo katalonok;;
+                go*)
+                    searches=$((searches + 1))
+                    if [ $searches = 1 ]; then sleep 0.7; echo "bestmove 00"; else echo "bestmove 22"; fi;;
+                quit) exit 0;;
+            esac
+        done"#;
+        let engine = fake(late).unwrap();
+        let board = board::Board::new();
+        assert!(matches!(
+            engine.try_play(&board),
+            Err(error::Error::Timeout)
+        ));
+        assert_eq!(engine.try_play(&board).unwrap(), board::Move::new(2, 2));
+    }
+
+    /// Test whether an engine which makes a move which cannot be made
+    /// forfeits the game.
+    #[test]
+    fn forfeit() {
+        let engine = fake(ANSWER).unwrap();
+        let mut game = game::Game::new(Box::new(engine), Box::new(random::Random), false);
+
+        assert_eq!(game.run(), board::Result::Player2);
+        let (player, error) = game.forfeit().unwrap();
+        assert_eq!(*player, player::Players::Player1);
+        assert!(matches!(error, error::Error::Move(_)));
+
+        let record = game.record();
+        assert_eq!(record.players, ["fake", "random"]);
+        assert_eq!(record.result, Some(board::Result::Player2));
+        assert_eq!(
+            record.termination,
+            Some(crate::record::Termination::Forfeit)
+        );
+    }
+}
@@ -0,0 +1,594 @@
+use crate::{board, error, eval, solver};
+
+// The C interface of the library, declared in include/katalon.h. Boards and
+// solvers are opaque handles which are created and freed by the library. The
+// functions which can fail return a status, and write their results to the
+// given pointers. Nothing panics across the interface, a panic is reported
+// as failed, null pointers and invalid text as an invalid argument.
+
+/// A board handed out to C.
+pub struct KatalonBoard {
+    board: board::Board,
+}
+
+/// A solver handed out to C, which keeps its table between searches.
+pub struct KatalonSolver {
+    session: solver::Session,
+}
+
+/// A move as a square and a cell, both from 0 to 4.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KatalonMove {
+    pub square: u8,
+    pub cell: u8,
+}
+
+/// An evaluation from the perspective of the player onturn, with the result
+/// -1 for a loss, 0 for a draw and 1 for a win.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KatalonEval {
+    pub result: i32,
+    pub distance: i32,
+}
+
+/// The status returned by the functions which can fail.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KatalonStatus {
+    Ok = 0,
+    /// A null pointer or text which is not UTF-8.
+    InvalidArgument = 1,
+    Notation = 2,
+    Move = 3,
+    Position = 4,
+    GameOver = 5,
+    Timeout = 6,
+    Failed = 7,
+}
+
+impl From<error::Error> for KatalonStatus {
+    fn from(error: error::Error) -> Self {
+        match error {
+            error::Error::Notation(_) | error::Error::Format(_) => KatalonStatus::Notation,
+            error::Error::Move(board::MoveError::GameOver) => KatalonStatus::GameOver,
+            error::Error::Move(_) => KatalonStatus::Move,
+            error::Error::Position(_) | error::Error::Rules(_) => KatalonStatus::Position,
+            error::Error::Timeout => KatalonStatus::Timeout,
+            error::Error::Cancelled
+            | error::Error::NodeLimit
+            | error::Error::DepthLimit
+            | error::Error::Io(_) => KatalonStatus::Failed,
+        }
+    }
+}
+
+impl From<eval::Eval> for KatalonEval {
+    fn from(value: eval::Eval) -> Self {
+        let (result, distance) = value.human();
+        let result = match result {
+            eval::Result::Loss => -1,
+            eval::Result::Draw => 0,
+            eval::Result::Win => 1,
+        };
+        Self {
+            result,
+            distance: distance as i32,
+        }
+    }
+}
+
+/// Run the body of an interface function, returning the fallback instead of
+/// unwinding into C if it panics.
+fn guard<T>(fallback: T, body: impl FnOnce() -> T) -> T {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(body)).unwrap_or(fallback)
+}
+
+/// Read a C string given by the caller.
+unsafe fn text<'a>(text: *const std::os::raw::c_char) -> Option<&'a str> {
+    if text.is_null() {
+        return None;
+    }
+    std::ffi::CStr::from_ptr(text).to_str().ok()
+}
+
+/// Hand the board out to the caller, who has to free it again.
+unsafe fn give(board: board::Board, out: *mut *mut KatalonBoard) -> KatalonStatus {
+    *out = Box::into_raw(Box::new(KatalonBoard { board }));
+    KatalonStatus::Ok
+}
+
+/// Copy as many moves as fit into the buffer of the caller, returning the
+/// number of moves there are.
+unsafe fn copy(moves: &[board::Move], buffer: *mut KatalonMove, capacity: usize) -> usize {
+    if !buffer.is_null() {
+        let buffer = std::slice::from_raw_parts_mut(buffer, capacity);
+        for (slot, mv) in buffer.iter_mut().zip(moves) {
+            *slot = KatalonMove {
+                square: mv.square,
+                cell: mv.cell,
+            };
+        }
+    }
+    moves.len()
+}
+
+/// Return the version of the library, the string is owned by the library.
+#[no_mangle]
+pub extern "C" fn katalon_version() -> *const std::os::raw::c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr() as *const _
+}
+
+/// Return a description of the status, the string is owned by the library.
+/// The status is taken as an integer, as C may pass any value.
+#[no_mangle]
+pub extern "C" fn katalon_status_message(status: i32) -> *const std::os::raw::c_char {
+    let message = match status {
+        0 => "ok\0",
+        1 => "invalid argument\0",
+        2 => "invalid notation\0",
+        3 => "the move cannot be made\0",
+        4 => "invalid position\0",
+        5 => "the game is already over\0",
+        6 => "the search did not finish before the timeout\0",
+        7 => "the search failed\0",
+        _ => "unknown status\0",
+    };
+    message.as_ptr() as *const _
+}
+
+/// Create the empty board.
+#[no_mangle]
+pub extern "C" fn katalon_board_new() -> *mut KatalonBoard {
+    guard(std::ptr::null_mut(), || {
+        Box::into_raw(Box::new(KatalonBoard {
+            board: board::Board::new(),
+        }))
+    })
+}
+
+/// Create a board from the moves played so far, see Board::load().
+///
+/// # Safety
+/// The moves have to be a C string and out a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn katalon_board_load(
+    moves: *const std::os::raw::c_char,
+    out: *mut *mut KatalonBoard,
+) -> KatalonStatus {
+    guard(KatalonStatus::Failed, || {
+        match (text(moves), out.is_null()) {
+            (Some(moves), false) => match board::Board::load(moves) {
+                Ok(board) => give(board, out),
+                Err(error) => error.into(),
+            },
+            _ => KatalonStatus::InvalidArgument,
+        }
+    })
+}
+
+/// Create a board from the position notation, see Board::from_fen().
+///
+/// # Safety
+/// The fen has to be a C string and out a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn katalon_board_from_fen(
+    fen: *const std::os::raw::c_char,
+    out: *mut *mut KatalonBoard,
+) -> KatalonStatus {
+    guard(KatalonStatus::Failed, || match (text(fen), out.is_null()) {
+        (Some(fen), false) => match board::Board::from_fen(fen) {
+            Ok(board) => give(board, out),
+            Err(error) => error.into(),
+        },
+        _ => KatalonStatus::InvalidArgument,
+    })
+}
+
+/// Copy the board, the copy has to be freed as well.
+///
+/// # Safety
+/// The board has to be null or a board created by this library.
+#[no_mangle]
+pub unsafe extern "C" fn katalon_board_clone(board: *const KatalonBoard) -> *mut KatalonBoard {
+    guard(std::ptr::null_mut(), || match board.as_ref() {
+        Some(board) => Box::into_raw(Box::new(KatalonBoard {
+            board: board.board.clone(),
+        })),
+        None => std::ptr::null_mut(),
+    })
+}
+
+/// Free the board, freeing null does nothing.
+///
+/// # Safety
+/// The board has to be null or a board created by this library, which is
+/// not used anymore afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn katalon_board_free(board: *mut KatalonBoard) {
+    if !board.is_null() {
+        drop(Box::from_raw(board));
+    }
+}
+
+/// Return the position notation of the board, which has to be freed with
+/// katalon_string_free(), or null if the board is null.
+///
+/// # Safety
+/// The board has to be null or a board created by this library.
+#[no_mangle]
+pub unsafe extern "C" fn katalon_board_to_fen(
+    board: *const KatalonBoard,
+) -> *mut std::os::raw::c_char {
+    guard(std::ptr::null_mut(), || match board.as_ref() {
+        Some(board) => std::ffi::CString::new(board.board.to_fen())
+            .map_or(std::ptr::null_mut(), std::ffi::CString::into_raw),
+        None => std::ptr::null_mut(),
+    })
+}
+
+/// Free a string returned by the library, freeing null does nothing.
+///
+/// # Safety
+/// The string has to be null or a string returned by katalon_board_to_fen().
+#[no_mangle]
+pub unsafe extern "C" fn katalon_string_free(string: *mut std::os::raw::c_char) {
+    if !string.is_null() {
+        drop(std::ffi::CString::from_raw(string));
+    }
+}
+
+/// Write the legal moves to the buffer, at most capacity of them, and return
+/// how many legal moves there are. There are never more than 25.
+///
+/// # Safety
+/// The board has to be null or a board created by this library, the buffer
+/// has to be null or hold capacity moves.
+#[no_mangle]
+pub unsafe extern "C" fn katalon_board_moves(
+    board: *const KatalonBoard,
+    buffer: *mut KatalonMove,
+    capacity: usize,
+) -> usize {
+    guard(0, || {
+        let board = match board.as_ref() {
+            Some(board) => &board.board,
+            None => return 0,
+        };
+        if board.isover().is_some() {
+            return 0;
+        }
+
+        // Board::moves() only gives one first move per symmetry.
+        let moves: Vec<board::Move> = match board.isfirst() {
+            true => (0..25)
+                .map(|index| board::Move::new(index / 5, index % 5))
+                .filter(|mv| board.canplay(mv.square, mv.cell))
+                .collect(),
+            false => board.moves(),
+        };
+        copy(&moves, buffer, capacity)
+    })
+}
+
+/// Play the move on the board if it can be made, a square or cell above 4
+/// is an invalid argument.
+///
+/// # Safety
+/// The board has to be null or a board created by this library.
+#[no_mangle]
+pub unsafe extern "C" fn katalon_board_play(
+    board: *mut KatalonBoard,
+    mv: KatalonMove,
+) -> KatalonStatus {
+    guard(KatalonStatus::Failed, || match board.as_mut() {
+        Some(board) if mv.square < 5 && mv.cell < 5 => {
+            match board.board.try_play(board::Move::new(mv.square, mv.cell)) {
+                Ok(()) => KatalonStatus::Ok,
+                Err(error) => error::Error::from(error).into(),
+            }
+        }
+        _ => KatalonStatus::InvalidArgument,
+    })
+}
+
+/// Return 0 if the game is not over, 1 or 2 if that player won and 3 for a
+/// draw, or -1 if the board is null.
+///
+/// # Safety
+/// The board has to be null or a board created by this library.
+#[no_mangle]
+pub unsafe extern "C" fn katalon_board_isover(board: *const KatalonBoard) -> i32 {
+    guard(-1, || {
+        match board.as_ref().map(|board| board.board.isover()) {
+            Some(None) => 0,
+            Some(Some(board::Result::Player1)) => 1,
+            Some(Some(board::Result::Player2)) => 2,
+            Some(Some(board::Result::Draw)) => 3,
+            None => -1,
+        }
+    })
+}
+
+/// Return the player onturn, 1 or 2, or -1 if the board is null.
+///
+/// # Safety
+/// The board has to be null or a board created by this library.
+#[no_mangle]
+pub unsafe extern "C" fn katalon_board_onturn(board: *const KatalonBoard) -> i32 {
+    guard(-1, || match board.as_ref() {
+        Some(board) => board.board.onturn() as i32 + 1,
+        None => -1,
+    })
+}
+
+/// Create a solver with a table of the given number of megabytes.
+#[no_mangle]
+pub extern "C" fn katalon_solver_new(megabytes: usize) -> *mut KatalonSolver {
+    guard(std::ptr::null_mut(), || {
+        Box::into_raw(Box::new(KatalonSolver {
+            session: solver::Session::from_gb(megabytes as f32 / 1000.0),
+        }))
+    })
+}
+
+/// Free the solver, freeing null does nothing.
+///
+/// # Safety
+/// The solver has to be null or a solver created by this library, which is
+/// not used anymore afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn katalon_solver_free(solver: *mut KatalonSolver) {
+    if !solver.is_null() {
+        drop(Box::from_raw(solver));
+    }
+}
+
+/// Evaluate the board within the timeout in milliseconds, a timeout of 0
+/// times out at once and u64::MAX does not limit the search.
+///
+/// # Safety
+/// The solver and the board have to be created by this library and out has
+/// to be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn katalon_eval(
+    solver: *mut KatalonSolver,
+    board: *const KatalonBoard,
+    timeout_ms: u64,
+    out: *mut KatalonEval,
+) -> KatalonStatus {
+    guard(KatalonStatus::Failed, || {
+        let (solver, board, out) = match (solver.as_mut(), board.as_ref(), out.as_mut()) {
+            (Some(solver), Some(board), Some(out)) => (solver, board, out),
+            _ => return KatalonStatus::InvalidArgument,
+        };
+
+        let timeout = std::time::Duration::from_millis(timeout_ms);
+        match solver.session.eval(&board.board, timeout).0 {
+            Ok(value) => {
+                *out = value.into();
+                KatalonStatus::Ok
+            }
+            Err(error) => error.into(),
+        }
+    })
+}
+
+/// Search the best moves of the board within the timeout in milliseconds,
+/// which is limited like the timeout of katalon_eval(). At most capacity of
+/// them are written to the buffer, count gets how many best moves there are
+/// and out their evaluation, both may be null.
+///
+/// # Safety
+/// The solver and the board have to be created by this library, the buffer
+/// has to be null or hold capacity moves.
+#[no_mangle]
+pub unsafe extern "C" fn katalon_bestmoves(
+    solver: *mut KatalonSolver,
+    board: *const KatalonBoard,
+    timeout_ms: u64,
+    buffer: *mut KatalonMove,
+    capacity: usize,
+    count: *mut usize,
+    out: *mut KatalonEval,
+) -> KatalonStatus {
+    guard(KatalonStatus::Failed, || {
+        let (solver, board) = match (solver.as_mut(), board.as_ref()) {
+            (Some(solver), Some(board)) => (solver, board),
+            _ => return KatalonStatus::InvalidArgument,
+        };
+
+        let timeout = std::time::Duration::from_millis(timeout_ms);
+        match solver.session.bestmoves(&board.board, timeout).0 {
+            Ok((value, moves)) => {
+                let total = copy(&moves, buffer, capacity);
+                if let Some(count) = count.as_mut() {
+                    *count = total;
+                }
+                if let Some(out) = out.as_mut() {
+                    *out = value.into();
+                }
+                KatalonStatus::Ok
+            }
+            Err(error) => error.into(),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Load a board through the interface.
+    fn load(moves: &str) -> (KatalonStatus, *mut KatalonBoard) {
+        let moves = std::ffi::CString::new(moves).unwrap();
+        let mut board = std::ptr::null_mut();
+        let status = unsafe { katalon_board_load(moves.as_ptr(), &mut board) };
+        (status, board)
+    }
+
+    /// Test whether boards are created, played on and freed.
+    #[test]
+    fn boards() {
+        unsafe {
+            let board = katalon_board_new();
+            let mut buffer = [KatalonMove { square: 0, cell: 0 }; 25];
+            assert_eq!(katalon_board_moves(board, buffer.as_mut_ptr(), 25), 25);
+            assert_eq!(katalon_board_onturn(board), 1);
+
+            let mv = KatalonMove { square: 0, cell: 1 };
+            assert_eq!(katalon_board_play(board, mv), KatalonStatus::Ok);
+            assert_eq!(katalon_board_play(board, mv), KatalonStatus::Move);
+            for mv in [
+                KatalonMove { square: 7, cell: 0 },
+                KatalonMove { square: 1, cell: 5 },
+            ] {
+                assert_eq!(
+                    katalon_board_play(board, mv),
+                    KatalonStatus::InvalidArgument
+                );
+            }
+            assert_eq!(katalon_board_moves(board, buffer.as_mut_ptr(), 2), 5);
+            assert_eq!(buffer[0], KatalonMove { square: 1, cell: 0 });
+            assert_eq!(katalon_board_onturn(board), 2);
+
+            let copy = katalon_board_clone(board);
+            katalon_board_free(board);
+            let fen = katalon_board_to_fen(copy);
+            let expected = board::Board::load("01").unwrap().to_fen();
+            assert_eq!(std::ffi::CStr::from_ptr(fen).to_str().unwrap(), expected);
+            katalon_string_free(fen);
+
+            let fen = std::ffi::CString::new(expected).unwrap();
+            let mut loaded = std::ptr::null_mut();
+            assert_eq!(
+                katalon_board_from_fen(fen.as_ptr(), &mut loaded),
+                KatalonStatus::Ok
+            );
+            assert_eq!((*loaded).board.key(), (*copy).board.key());
+            katalon_board_free(loaded);
+            katalon_board_free(copy);
+
+            let (status, over) = load("2320212422");
+            assert_eq!(status, KatalonStatus::Ok);
+            assert_eq!(katalon_board_isover(over), 1);
+            assert_eq!(katalon_board_moves(over, std::ptr::null_mut(), 0), 0);
+            assert_eq!(
+                katalon_board_play(over, KatalonMove { square: 2, cell: 0 }),
+                KatalonStatus::GameOver
+            );
+            katalon_board_free(over);
+        }
+    }
+
+    /// Test whether invalid arguments are reported instead of crashing.
+    #[test]
+    fn invalid() {
+        unsafe {
+            assert_eq!(load("5").0, KatalonStatus::Notation);
+            assert_eq!(load("000").0, KatalonStatus::Move);
+            assert_eq!(
+                katalon_board_load(std::ptr::null(), &mut std::ptr::null_mut()),
+                KatalonStatus::InvalidArgument
+            );
+
+            let null = std::ptr::null_mut();
+            assert_eq!(katalon_board_isover(null), -1);
+            assert_eq!(katalon_board_moves(null, null as *mut _, 0), 0);
+            assert!(katalon_board_clone(null).is_null());
+            assert!(katalon_board_to_fen(null).is_null());
+            katalon_board_free(null);
+            katalon_string_free(std::ptr::null_mut());
+            assert_eq!(
+                katalon_eval(std::ptr::null_mut(), null, 0, std::ptr::null_mut()),
+                KatalonStatus::InvalidArgument
+            );
+
+            let message = |status| std::ffi::CStr::from_ptr(katalon_status_message(status));
+            assert_eq!(
+                message(KatalonStatus::Move as i32).to_str().unwrap(),
+                "the move cannot be made"
+            );
+            assert_eq!(message(8).to_str().unwrap(), "unknown status");
+            assert_eq!(message(-1).to_str().unwrap(), "unknown status");
+            let version = std::ffi::CStr::from_ptr(katalon_version());
+            assert_eq!(version.to_str().unwrap(), env!("CARGO_PKG_VERSION"));
+        }
+    }
+
+    /// Test whether positions are evaluated and the best moves are given.
+    #[test]
+    fn search() {
+        unsafe {
+            let solver = katalon_solver_new(10);
+            let (_, board) = load("12104023441433032242301121");
+
+            let mut value = KatalonEval {
+                result: 0,
+                distance: 0,
+            };
+            let status = katalon_eval(solver, board, 60_000, &mut value);
+            assert_eq!(status, KatalonStatus::Ok);
+            assert_eq!(
+                value,
+                KatalonEval {
+                    result: 1,
+                    distance: 2
+                }
+            );
+
+            let mut buffer = [KatalonMove { square: 0, cell: 0 }; 25];
+            let mut count = 0;
+            let status = katalon_bestmoves(
+                solver,
+                board,
+                60_000,
+                buffer.as_mut_ptr(),
+                25,
+                &mut count,
+                std::ptr::null_mut(),
+            );
+            assert_eq!(status, KatalonStatus::Ok);
+            assert!(count >= 1);
+            assert_eq!(katalon_board_play(board, buffer[0]), KatalonStatus::Ok);
+
+            let (_, start) = load("0123");
+            assert_eq!(
+                katalon_eval(solver, start, 0, &mut value),
+                KatalonStatus::Timeout
+            );
+
+            katalon_board_free(start);
+            katalon_board_free(board);
+            katalon_solver_free(solver);
+        }
+    }
+
+    /// Test whether the header declares all the functions.
+    #[test]
+    fn header() {
+        let header = include_str!("../include/katalon.h");
+        for function in [
+            "katalon_version",
+            "katalon_status_message",
+            "katalon_board_new",
+            "katalon_board_load",
+            "katalon_board_from_fen",
+            "katalon_board_clone",
+            "katalon_board_free",
+            "katalon_board_to_fen",
+            "katalon_string_free",
+            "katalon_board_moves",
+            "katalon_board_play",
+            "katalon_board_isover",
+            "katalon_board_onturn",
+            "katalon_solver_new",
+            "katalon_solver_free",
+            "katalon_eval",
+            "katalon_bestmoves",
+        ] {
+            assert!(header.contains(&format!("{}(", function)), "{}", function);
+        }
+    }
+}
@@ -1,11 +1,15 @@
-use crate::{board, player};
+use crate::{board, error, player, record};
 
 type Player = Box<dyn player::Player>;
 
 pub struct Game {
     players: [Player; 2],
     board: board::Board,
+    /// The moves made so far.
+    moves: Vec<board::Move>,
     verbose: bool,
+    /// The player who forfeited the game and why, if any.
+    forfeit: Option<(player::Players, error::Error)>,
 }
 
 impl Game {
@@ -13,10 +17,59 @@ impl Game {
         Self {
             players: [player1, player2],
             board: board::Board::new(),
+            moves: Vec::new(),
             verbose,
+            forfeit: None,
         }
     }
 
+    /// Continue the game of the record, which should not be over yet.
+    pub fn resume(
+        player1: Player,
+        player2: Player,
+        record: &record::Record,
+        verbose: bool,
+    ) -> error::Result<Self> {
+        let board = record.board()?;
+        if board.isover().is_some() {
+            return Err(error::Error::Move(board::MoveError::GameOver));
+        }
+
+        Ok(Self {
+            players: [player1, player2],
+            board,
+            moves: record.moves.iter().map(|annotated| annotated.mv).collect(),
+            verbose,
+            forfeit: None,
+        })
+    }
+
+    /// Return the record of the game so far.
+    pub fn record(&self) -> record::Record {
+        let mut record = record::Record::from_moves(*self.board.rules(), &self.moves)
+            .expect("The moves were made on the board.");
+        record.players = [self.players[0].name(), self.players[1].name()];
+        record.date = Some(record::today());
+
+        if let Some((player, error)) = &self.forfeit {
+            record.result = Some(board::Result::from_player(&player.other()));
+            record.termination = Some(record::Termination::Forfeit);
+            let reason = error.to_string().lines().collect::<Vec<_>>().join(" ");
+            record.headers.push((String::from("Forfeit"), reason));
+        }
+        record
+    }
+
+    /// Add the record of the game so far to the end of the file.
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> error::Result<()> {
+        record::append(path, &self.record())
+    }
+
+    /// Return the player who forfeited the game and why, if a player did.
+    pub fn forfeit(&self) -> Option<&(player::Players, error::Error)> {
+        self.forfeit.as_ref()
+    }
+
     /// Play the game and return the result of the round.
     pub fn run(&mut self) -> board::Result {
         if self.board.isover() != None {
@@ -29,8 +82,20 @@ impl Game {
 
         while self.board.isover() == None {
             let player = &self.players[self.board.onturn() as usize];
-            let (square, cell) = player.play(&self.board);
-            self.board.play(square, cell);
+            let mv = match player.try_play(&self.board) {
+                Ok(mv) => mv,
+                Err(error) => {
+                    // A player who fails to make a move loses the game.
+                    let onturn = self.board.onturn();
+                    if self.verbose {
+                        println!("Player {} forfeits: {}", onturn, error);
+                    }
+                    self.forfeit = Some((onturn, error));
+                    return board::Result::from_player(&onturn.other());
+                }
+            };
+            self.board.play(mv.square, mv.cell);
+            self.moves.push(mv);
 
             if self.verbose {
                 print!("\n{}", self.board);
@@ -39,3 +104,52 @@ impl Game {
         self.board.isover().unwrap()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random;
+
+    /// Test whether a game is recorded and can be resumed from its record.
+    #[test]
+    fn records() {
+        let mut game = Game::new(Box::new(random::Random), Box::new(random::Random), false);
+        let result = game.run();
+
+        let record = game.record();
+        assert_eq!(record.players, ["random", "random"]);
+        assert_eq!(record.result, Some(result));
+        assert!(matches!(
+            record.termination,
+            Some(record::Termination::Board(_))
+        ));
+        assert_eq!(record.board().unwrap().key(), game.board.key());
+        assert_eq!(
+            record.to_string().parse::<record::Record>().unwrap(),
+            record
+        );
+
+        let unfinished =
+            record::Record::from_moves(board::Rules::default(), &game.moves[..3]).unwrap();
+        let mut resumed = Game::resume(
+            Box::new(random::Random),
+            Box::new(random::Random),
+            &unfinished,
+            false,
+        )
+        .unwrap();
+        assert_eq!(resumed.board.movecount(), 3);
+        resumed.run();
+        assert_eq!(&resumed.moves[..3], &game.moves[..3]);
+
+        assert!(matches!(
+            Game::resume(
+                Box::new(random::Random),
+                Box::new(random::Random),
+                &record,
+                false
+            ),
+            Err(error::Error::Move(board::MoveError::GameOver))
+        ));
+    }
+}
@@ -4,7 +4,7 @@ use crate::{board, input, player};
 pub struct Human;
 
 impl player::Player for Human {
-    fn play(&self, board: &board::Board) -> (u8, u8) {
+    fn play(&self, board: &board::Board) -> board::Move {
         loop {
             let line = input::request(format!("{} > ", board.onturn()));
             let re = input::move_regex();
@@ -15,12 +15,16 @@ impl player::Player for Human {
             }
 
             match input::extract(board, &line) {
-                Ok((square, cell)) => return (square, cell),
+                Ok(mv) => return mv,
                 Err(e) => {
-                    println!("{}", e);
+                    println!("Error: {}", e);
                     continue;
                 }
             }
         }
     }
+
+    fn name(&self) -> String {
+        String::from("human")
+    }
 }
@@ -1,7 +1,12 @@
 // #![warn(missing_docs)]
 
+#[cfg(feature = "analysis")]
+pub mod analysis;
+pub mod annotate;
 pub mod board;
+pub mod error;
 pub mod game;
+pub mod perft;
 
 pub mod player;
 
@@ -9,7 +14,15 @@ pub mod human;
 pub mod input;
 
 pub mod random;
+pub mod record;
 
+#[cfg(feature = "serve")]
+pub mod server;
+
+pub mod engine;
 pub mod eval;
+pub mod external;
+pub mod ffi;
 pub mod solver;
 pub mod stats;
+pub mod verify;
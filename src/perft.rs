@@ -0,0 +1,203 @@
+use crate::board;
+use std::collections::HashSet;
+
+/// The number of positions reached after a given number of moves.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counts {
+    /// The number of move sequences of this length.
+    pub total: usize,
+    /// The number of different positions, symmetrical positions count as one.
+    pub unique: usize,
+    /// The number of move sequences which ended in a win for player1.
+    pub player1: usize,
+    /// The number of move sequences which ended in a win for player2.
+    pub player2: usize,
+    /// The number of move sequences which ended in a draw.
+    pub draw: usize,
+}
+
+impl Counts {
+    /// Return the number of move sequences which ended the game.
+    pub fn gameover(&self) -> usize {
+        self.player1 + self.player2 + self.draw
+    }
+}
+
+/// The counts and the keys of the unique positions for every depth.
+struct Perft {
+    counts: Vec<Counts>,
+    keys: Vec<HashSet<u64>>,
+}
+
+impl Perft {
+    fn new(depth: usize) -> Self {
+        Self {
+            counts: vec![Counts::default(); depth + 1],
+            keys: vec![HashSet::new(); depth + 1],
+        }
+    }
+
+    /// Add the counts and keys of the other perft, which started at the given depth.
+    fn merge(&mut self, other: Perft, depth: usize) {
+        for (index, (counts, keys)) in other.counts.into_iter().zip(other.keys).enumerate() {
+            let own = &mut self.counts[depth + index];
+            own.total += counts.total;
+            own.player1 += counts.player1;
+            own.player2 += counts.player2;
+            own.draw += counts.draw;
+            self.keys[depth + index].extend(keys);
+        }
+    }
+}
+
+/// Return all the moves that can be made. Unlike Board::moves() all 25 first
+/// moves are returned and canplay() is used, such that perft checks the rules.
+fn moves(board: &board::Board) -> Vec<(u8, u8)> {
+    match board.square() {
+        None => (0..25)
+            .map(|index| (index / 5, index % 5))
+            .filter(|&(square, cell)| board.canplay(square, cell))
+            .collect(),
+        Some(square) => (0..5)
+            .filter(|&cell| board.canplay(square, cell))
+            .map(|cell| (square, cell))
+            .collect(),
+    }
+}
+
+/// Count the current board and walk through all the boards reachable within
+/// the given depth. Finished games are counted, but not continued.
+fn walk(board: &mut board::Board, ply: usize, depth: usize, perft: &mut Perft) {
+    perft.keys[ply].insert(board.canonical_key());
+
+    let counts = &mut perft.counts[ply];
+    counts.total += 1;
+    match board.isover() {
+        Some(board::Result::Player1) => return counts.player1 += 1,
+        Some(board::Result::Player2) => return counts.player2 += 1,
+        Some(board::Result::Draw) => return counts.draw += 1,
+        None => (),
+    }
+
+    if ply == depth {
+        return;
+    }
+
+    for (square, cell) in moves(board) {
+        let undo = board.play(square, cell);
+        walk(board, ply + 1, depth, perft);
+        board.unplay(undo);
+    }
+}
+
+/// Count the positions reachable from the given board for every number of
+/// moves from 0 up to and including the depth. If parallel is set, every move
+/// from the given board is counted in its own thread.
+pub fn perft(board: &board::Board, depth: usize, parallel: bool) -> Vec<Counts> {
+    let mut perft = Perft::new(depth);
+
+    if !parallel || depth == 0 || board.isover().is_some() {
+        walk(&mut board.clone(), 0, depth, &mut perft);
+    } else {
+        // Count the root here and all the children in their own thread.
+        perft.counts[0].total = 1;
+        perft.keys[0].insert(board.canonical_key());
+
+        let children = std::thread::scope(|scope| {
+            let handles: Vec<_> = moves(board)
+                .into_iter()
+                .map(|(square, cell)| {
+                    let mut child = board.clone();
+                    child.play(square, cell);
+                    scope.spawn(move || {
+                        let mut perft = Perft::new(depth - 1);
+                        walk(&mut child, 0, depth - 1, &mut perft);
+                        perft
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        for child in children {
+            perft.merge(child, 1);
+        }
+    }
+
+    for (counts, keys) in perft.counts.iter_mut().zip(&perft.keys) {
+        counts.unique = keys.len();
+    }
+    perft.counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Check the counts from the empty board against known good numbers.
+    #[test]
+    fn perft_empty() {
+        let counts = perft(&board::Board::new(), 6, false);
+        let total: Vec<usize> = counts.iter().map(|counts| counts.total).collect();
+        let unique: Vec<usize> = counts.iter().map(|counts| counts.unique).collect();
+
+        assert_eq!(total, TOTAL_EMPTY);
+        assert_eq!(unique, UNIQUE_EMPTY);
+        assert!(counts.iter().all(|counts| counts.gameover() == 0));
+    }
+
+    /// Check the counts of finished games against known good numbers.
+    #[test]
+    fn perft_gameover() {
+        let board = board::Board::load("221400203101122").unwrap();
+        let counts = perft(&board, 9, false);
+        let gameover: Vec<(usize, usize, usize)> = counts
+            .iter()
+            .map(|counts| (counts.player1, counts.player2, counts.draw))
+            .collect();
+
+        assert_eq!(gameover, GAMEOVER);
+    }
+
+    /// Check the counts of draws by the takestreak against known good numbers.
+    #[test]
+    fn perft_draw() {
+        // The only possible move takes a stone and reaches the takestreak limit.
+        let cycle = "21103".repeat(6);
+        let moves = String::from("20033102212432011410302234201")
+            + &cycle[..board::Board::TAKESTREAK_LIMIT as usize - 3];
+        let board = board::Board::load(&moves).unwrap();
+
+        let counts = perft(&board, 1, false);
+        let expected = Counts {
+            total: 1,
+            unique: 1,
+            draw: 1,
+            ..Counts::default()
+        };
+        assert_eq!(counts[1], expected);
+    }
+
+    /// Check whether counting in parallel gives the same counts.
+    #[test]
+    fn perft_parallel() {
+        let board = board::Board::load("221400203101122").unwrap();
+        assert_eq!(perft(&board, 5, true), perft(&board, 5, false));
+        assert_eq!(
+            perft(&board::Board::new(), 4, true),
+            perft(&board::Board::new(), 4, false)
+        );
+    }
+
+    const TOTAL_EMPTY: [usize; 7] = [1, 25, 116, 512, 2128, 8376, 30908];
+    const UNIQUE_EMPTY: [usize; 7] = [1, 6, 20, 75, 279, 1022, 3489];
+    #[rustfmt::skip]
+    const GAMEOVER: [(usize, usize, usize); 10] = [
+        (0, 0, 0), (0, 0, 0), (0, 0, 0), (0, 0, 0), (0, 0, 0),
+        (3, 0, 0), (0, 1, 0), (17, 0, 0), (0, 23, 0), (82, 0, 0),
+    ];
+}
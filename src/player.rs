@@ -1,22 +1,36 @@
-use crate::board;
+use crate::{board, error};
 use Players::*;
 
 pub trait Player {
-    fn play(&self, board: &board::Board) -> (u8, u8);
+    fn play(&self, board: &board::Board) -> board::Move;
+
+    /// Return the move to make, or an error if the player fails to make one,
+    /// in which case the player forfeits the game.
+    fn try_play(&self, board: &board::Board) -> error::Result<board::Move> {
+        Ok(self.play(board))
+    }
+
+    /// Return the name of the player, e.g. for game records.
+    fn name(&self) -> String {
+        String::from("unknown")
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Players {
     Player1,
     Player2,
 }
 
 impl Players {
-    pub fn from_index(index: usize) -> Result<Players, ()> {
+    pub fn from_index(index: usize) -> error::Result<Players> {
         match index {
             0 => Ok(Player1),
             1 => Ok(Player2),
-            _ => Err(()),
+            _ => Err(error::Error::Format(format!(
+                "The player index should be 0 or 1, not {}.",
+                index
+            ))),
         }
     }
 
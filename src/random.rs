@@ -5,13 +5,15 @@ use rand::Rng;
 pub struct Random;
 
 impl player::Player for Random {
-    fn play(&self, board: &board::Board) -> (u8, u8) {
+    fn play(&self, board: &board::Board) -> board::Move {
         let mut rng = rand::thread_rng();
 
         if board.isfirst() {
-            let square: u8 = rng.gen_range(0..=4);
-            let cell: u8 = rng.gen_range(0..=4);
-            return (square, cell);
+            let options: Vec<_> = (0..25)
+                .map(|index| board::Move::new(index / 5, index % 5))
+                .filter(|mv| board.canplay(mv.square, mv.cell))
+                .collect();
+            return options[rng.gen_range(0..options.len())];
         } else {
             let square = board.square().unwrap();
             let options: Vec<_> = vec![0, 1, 2, 3, 4]
@@ -19,7 +21,11 @@ impl player::Player for Random {
                 .filter(|&cell| board.canplay(square, cell))
                 .collect();
             let cell = options[rng.gen_range(0..options.len()) as usize];
-            return (square, cell);
+            return board::Move::new(square, cell);
         }
     }
+
+    fn name(&self) -> String {
+        String::from("random")
+    }
 }
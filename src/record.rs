@@ -0,0 +1,666 @@
+use crate::{board, error, eval};
+
+// A game record is a text format similar to PGN in chess, e.g.
+//
+//   [Player1 "solver"]
+//   [Player2 "random"]
+//   [Date "2026.10.18"]
+//   [Rules "stones=12 takestreak=15 firstmove=free fullboard=3"]
+//   [Result "1-0"]
+//   [Termination "square"]
+//
+//   1. 22 20 {a comment} 2. 03 {[%eval win 5]} 31 1-0
+//
+// The headers are a name and a quoted value in brackets, in which quotes and
+// backslashes are escaped with a backslash. The moves are written as square
+// and cell and may be followed by a comment in braces, in which closing braces
+// and backslashes are escaped the same way. The comment can start with the
+// evaluation after the move from the perspective of the player who made it,
+// so a bracket at the start of the text of a comment is escaped as well.
+// Move numbers count the moves of both players together and are ignored when
+// read. The result ends the game: 1-0 if player1 won, 0-1 if player2 won,
+// 1/2-1/2 for a draw and * if the game did not finish. A file can hold
+// multiple games after each other.
+
+/// Why a recorded game ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Termination {
+    /// The game is over on the board.
+    Board(board::Termination),
+    /// A player failed to make a move.
+    Forfeit,
+}
+
+impl std::fmt::Display for Termination {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Termination::Board(termination) => write!(f, "{}", termination),
+            Termination::Forfeit => write!(f, "forfeit"),
+        }
+    }
+}
+
+impl std::str::FromStr for Termination {
+    type Err = error::Error;
+
+    fn from_str(text: &str) -> error::Result<Self> {
+        match text {
+            "forfeit" => Ok(Termination::Forfeit),
+            text => Ok(Termination::Board(text.parse()?)),
+        }
+    }
+}
+
+/// A move of a record together with its annotations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Annotated {
+    pub mv: board::Move,
+    /// The evaluation after the move, from the perspective of the player who
+    /// made the move.
+    pub eval: Option<eval::Eval>,
+    pub comment: Option<String>,
+}
+
+impl From<board::Move> for Annotated {
+    fn from(mv: board::Move) -> Self {
+        Self {
+            mv,
+            eval: None,
+            comment: None,
+        }
+    }
+}
+
+/// The record of a single game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    /// The names of player1 and player2.
+    pub players: [String; 2],
+    /// The date the game was played as YYYY.MM.DD.
+    pub date: Option<String>,
+    pub rules: board::Rules,
+    /// The result, or None if the game did not finish.
+    pub result: Option<board::Result>,
+    pub termination: Option<Termination>,
+    /// Any other headers, in the order they were read.
+    pub headers: Vec<(String, String)>,
+    pub moves: Vec<Annotated>,
+}
+
+impl Record {
+    /// Create an empty record of unknown players.
+    pub fn new() -> Self {
+        Self {
+            players: [String::from("?"), String::from("?")],
+            date: None,
+            rules: board::Rules::default(),
+            result: None,
+            termination: None,
+            headers: Vec::new(),
+            moves: Vec::new(),
+        }
+    }
+
+    /// Create a record of the moves played with the rules, taking the result
+    /// from the board if the game is over.
+    pub fn from_moves(rules: board::Rules, moves: &[board::Move]) -> error::Result<Self> {
+        let mut record = Self::new();
+        record.rules = rules;
+        record.moves = moves.iter().map(|&mv| Annotated::from(mv)).collect();
+
+        let board = record.board()?;
+        record.result = board.isover();
+        record.termination = board.termination().map(Termination::Board);
+        Ok(record)
+    }
+
+    /// Replay the moves and return the board after the last one.
+    pub fn board(&self) -> error::Result<board::Board> {
+        let mut board = board::Board::with_rules(self.rules);
+        for annotated in &self.moves {
+            board.try_play(annotated.mv)?;
+        }
+        Ok(board)
+    }
+
+    /// Read all the games from the text.
+    pub fn parse_all(text: &str) -> error::Result<Vec<Self>> {
+        let mut records = Vec::new();
+        let mut record = Self::new();
+        // Whether the headers are read and the moves have started.
+        let mut started = false;
+        let mut chars = text.chars().peekable();
+
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() {
+                chars.next();
+            } else if c == '[' && !started {
+                chars.next();
+                let (name, value) = header(&mut chars)?;
+                record.header(name, value)?;
+            } else if c == '{' {
+                chars.next();
+                // The escapes are kept, so an escaped bracket at the start is
+                // not taken for an evaluation.
+                let mut comment = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => {
+                            comment.push(c);
+                            comment.extend(chars.next());
+                        }
+                        '}' => break,
+                        c => comment.push(c),
+                    }
+                }
+                let annotated = record.moves.last_mut().ok_or_else(|| {
+                    error::Error::Notation(String::from("A comment should follow a move."))
+                })?;
+                annotate(annotated, &comment)?;
+            } else {
+                started = true;
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '{' {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+
+                if let Some(result) = parse_result(&word) {
+                    record.finish(result)?;
+                    records.push(std::mem::take(&mut record));
+                    started = false;
+                } else if !is_movenumber(&word) {
+                    record.moves.push(Annotated::from(parse_move(&word)?));
+                }
+            }
+        }
+
+        if started || record != Self::new() {
+            return Err(error::Error::Notation(String::from(
+                "The last game should end with a result.",
+            )));
+        }
+        Ok(records)
+    }
+
+    /// Set a header read from the text.
+    fn header(&mut self, name: String, value: String) -> error::Result<()> {
+        match name.as_str() {
+            "Player1" => self.players[0] = value,
+            "Player2" => self.players[1] = value,
+            "Date" => self.date = Some(value),
+            "Rules" => self.rules = parse_rules(&value)?,
+            "Result" => {
+                self.result = parse_result(&value)
+                    .ok_or_else(|| error::Error::Notation(format!("Unknown result {}.", value)))?
+            }
+            "Termination" => self.termination = Some(value.parse()?),
+            _ => self.headers.push((name, value)),
+        }
+        Ok(())
+    }
+
+    /// Check the game once its result is read.
+    fn finish(&mut self, result: Option<board::Result>) -> error::Result<()> {
+        if self.result.is_some() && self.result != result {
+            return Err(error::Error::Notation(String::from(
+                "The result after the moves differs from the Result header.",
+            )));
+        }
+        self.result = result;
+
+        let board = self.board()?;
+        if board.isover().is_some() && board.isover() != result {
+            return Err(error::Error::Notation(String::from(
+                "The result differs from the result on the board.",
+            )));
+        }
+
+        // A game is either over on the board or forfeited.
+        let termination = match self.termination {
+            Some(Termination::Board(termination)) => board.termination() == Some(termination),
+            Some(Termination::Forfeit) => board.isover().is_none(),
+            None => true,
+        };
+        if !termination {
+            return Err(error::Error::Notation(String::from(
+                "The termination differs from the end of the game on the board.",
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Default for Record {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::str::FromStr for Record {
+    type Err = error::Error;
+
+    /// Read a text holding a single game.
+    fn from_str(text: &str) -> error::Result<Self> {
+        let mut records = Self::parse_all(text)?;
+        match records.len() {
+            1 => Ok(records.remove(0)),
+            count => Err(error::Error::Notation(format!(
+                "Expected a single game, not {}.",
+                count
+            ))),
+        }
+    }
+}
+
+impl std::fmt::Display for Record {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut headers = vec![
+            ("Player1", self.players[0].clone()),
+            ("Player2", self.players[1].clone()),
+        ];
+        if let Some(date) = &self.date {
+            headers.push(("Date", date.clone()));
+        }
+        headers.push(("Rules", format_rules(&self.rules)));
+        headers.push(("Result", format_result(self.result).to_string()));
+        if let Some(termination) = self.termination {
+            headers.push(("Termination", termination.to_string()));
+        }
+        for (name, value) in &self.headers {
+            headers.push((name, value.clone()));
+        }
+
+        for (name, value) in headers {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            writeln!(f, "[{} \"{}\"]", name, value)?;
+        }
+        writeln!(f)?;
+
+        // The moves are wrapped to lines of at most 80 characters.
+        let mut tokens = Vec::new();
+        for (index, annotated) in self.moves.iter().enumerate() {
+            if index % 2 == 0 {
+                tokens.push(format!("{}.", index / 2 + 1));
+            }
+            tokens.push(annotated.mv.to_string());
+
+            let mut comment = Vec::new();
+            if let Some(value) = annotated.eval {
+                let (result, distance) = value.human();
+                comment.push(format!("[%eval {} {}]", result, distance));
+            }
+            if let Some(text) = &annotated.comment {
+                let mut text = text.replace('\\', "\\\\").replace('}', "\\}");
+                // Only a bracket at the start can be taken for an evaluation.
+                if text.trim_start().starts_with('[') {
+                    text.insert(text.len() - text.trim_start().len(), '\\');
+                }
+                comment.push(text);
+            }
+            if !comment.is_empty() {
+                tokens.push(format!("{{{}}}", comment.join(" ")));
+            }
+        }
+        tokens.push(format_result(self.result).to_string());
+
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > 80 {
+                writeln!(f, "{}", line)?;
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        writeln!(f, "{}", line)
+    }
+}
+
+/// Read the name and the value of a header, after its opening bracket.
+fn header(chars: &mut std::iter::Peekable<std::str::Chars>) -> error::Result<(String, String)> {
+    let invalid =
+        || error::Error::Notation(String::from("Please use headers like [Name \"value\"]."));
+
+    let name: String = chars.by_ref().take_while(|&c| c != ' ').collect();
+    if name.is_empty() || chars.next() != Some('"') {
+        return Err(invalid());
+    }
+
+    let mut value = String::new();
+    loop {
+        match chars.next().ok_or_else(invalid)? {
+            '\\' => value.push(chars.next().ok_or_else(invalid)?),
+            '"' => break,
+            c => value.push(c),
+        }
+    }
+
+    match chars.next() {
+        Some(']') => Ok((name, value)),
+        _ => Err(invalid()),
+    }
+}
+
+/// Add the evaluation at the start and the text of a comment to the move,
+/// the comment still has its escapes.
+fn annotate(annotated: &mut Annotated, comment: &str) -> error::Result<()> {
+    let mut text = comment.trim_start();
+    if let Some(rest) = text.strip_prefix("[%eval ") {
+        let end = rest.find(']').ok_or_else(|| {
+            error::Error::Notation(String::from("Please use evaluations like [%eval win 3]."))
+        })?;
+        annotated.eval = Some(parse_eval(&rest[..end])?);
+        text = &rest[end + 1..];
+    }
+
+    let mut unescaped = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            c => unescaped.push(c),
+        }
+    }
+    let text = unescaped.trim();
+    if !text.is_empty() {
+        annotated.comment = Some(text.to_string());
+    }
+    Ok(())
+}
+
+/// Read an evaluation written as result and distance, e.g. "win 3".
+fn parse_eval(text: &str) -> error::Result<eval::Eval> {
+    let invalid = || error::Error::Notation(format!("Invalid evaluation {}.", text));
+
+    let (result, distance) = match text.split_whitespace().collect::<Vec<_>>()[..] {
+        [result, distance] => (result, distance.parse::<i16>().map_err(|_| invalid())?),
+        _ => return Err(invalid()),
+    };
+    let result = match result {
+        "win" => eval::Result::Win,
+        "draw" => eval::Result::Draw,
+        "loss" => eval::Result::Loss,
+        _ => return Err(invalid()),
+    };
+    eval::Eval::try_from_human(result, distance).ok_or_else(invalid)
+}
+
+fn parse_move(text: &str) -> error::Result<board::Move> {
+    text.parse::<board::Move>().map_err(|_| {
+        error::Error::Notation(format!(
+            "Unknown move {}, please use a square and a cell.",
+            text
+        ))
+    })
+}
+
+fn is_movenumber(text: &str) -> bool {
+    match text.strip_suffix('.') {
+        Some(number) => !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()),
+        None => false,
+    }
+}
+
+/// Read a result, which is None if the game did not finish.
+fn parse_result(text: &str) -> Option<Option<board::Result>> {
+    match text {
+        "1-0" => Some(Some(board::Result::Player1)),
+        "0-1" => Some(Some(board::Result::Player2)),
+        "1/2-1/2" => Some(Some(board::Result::Draw)),
+        "*" => Some(None),
+        _ => None,
+    }
+}
+
+fn format_result(result: Option<board::Result>) -> &'static str {
+    match result {
+        Some(board::Result::Player1) => "1-0",
+        Some(board::Result::Player2) => "0-1",
+        Some(board::Result::Draw) => "1/2-1/2",
+        None => "*",
+    }
+}
+
+fn format_rules(rules: &board::Rules) -> String {
+    format!(
+        "stones={} takestreak={} firstmove={} fullboard={}",
+        rules.stones(),
+        rules.takestreak_limit(),
+        if rules.first_move_restricted() {
+            "restricted"
+        } else {
+            "free"
+        },
+        rules.full_board_squares(),
+    )
+}
+
+/// Read the rules, the settings which are left out are the default ones.
+fn parse_rules(text: &str) -> error::Result<board::Rules> {
+    let default = board::Rules::default();
+    let (mut stones, mut takestreak) = (default.stones(), default.takestreak_limit());
+    let (mut restricted, mut fullboard) = (
+        default.first_move_restricted(),
+        default.full_board_squares(),
+    );
+
+    for setting in text.split_whitespace() {
+        let invalid = || error::Error::Rules(format!("Unknown setting {}.", setting));
+        let (name, value) = setting.split_once('=').ok_or_else(invalid)?;
+        match (name, value) {
+            ("stones", value) => stones = value.parse().map_err(|_| invalid())?,
+            ("takestreak", value) => takestreak = value.parse().map_err(|_| invalid())?,
+            ("firstmove", "free") => restricted = false,
+            ("firstmove", "restricted") => restricted = true,
+            ("fullboard", value) => fullboard = value.parse().map_err(|_| invalid())?,
+            _ => return Err(invalid()),
+        }
+    }
+    board::Rules::new(stones, takestreak, restricted, fullboard)
+}
+
+/// Read all the games of the file.
+pub fn load(path: impl AsRef<std::path::Path>) -> error::Result<Vec<Record>> {
+    Record::parse_all(&std::fs::read_to_string(path)?)
+}
+
+/// Add the game to the end of the file, creating it if needed.
+pub fn append(path: impl AsRef<std::path::Path>, record: &Record) -> error::Result<()> {
+    use std::io::Write;
+
+    let path = path.as_ref();
+    let empty = std::fs::metadata(path).map_or(true, |metadata| metadata.len() == 0);
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+
+    // The games are separated by an empty line.
+    if !empty {
+        writeln!(file)?;
+    }
+    write!(file, "{}", record)?;
+    Ok(())
+}
+
+/// Today's date as YYYY.MM.DD in UTC.
+pub fn today() -> String {
+    let seconds = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let (year, month, day) = civil(seconds as i64 / 86400);
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
+
+/// Convert the days since 1970-01-01 to a year, month and day.
+fn civil(days: i64) -> (i64, i64, i64) {
+    // See http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GAME: &str = r#"[Player1 "solver"]
+[Player2 "random \"the second\""]
+[Date "2026.10.18"]
+[Rules "stones=12 takestreak=15 firstmove=free fullboard=3"]
+[Result "1-0"]
+[Termination "square"]
+[Event "test"]
+
+1. 23 32 {[%eval win 7] takes nothing} 2. 20 02 3. 21 {the end is near} 12 4. 24
+42 5. 22 {[%eval win 0]} 1-0
+"#;
+
+    /// Test whether a game is read with all its headers and annotations.
+    #[test]
+    fn parse() {
+        let record: Record = GAME.parse().unwrap();
+        assert_eq!(record.players, ["solver", "random \"the second\""]);
+        assert_eq!(record.date.as_deref(), Some("2026.10.18"));
+        assert_eq!(record.rules, board::Rules::default());
+        assert_eq!(record.result, Some(board::Result::Player1));
+        assert_eq!(
+            record.termination,
+            Some(Termination::Board(board::Termination::Square))
+        );
+        assert_eq!(
+            record.headers,
+            vec![(String::from("Event"), String::from("test"))]
+        );
+
+        assert_eq!(record.moves.len(), 9);
+        assert_eq!(record.moves[0], Annotated::from(board::Move::new(2, 3)));
+        assert_eq!(
+            record.moves[1].eval,
+            Some(eval::Eval::from(eval::Result::Win, 7))
+        );
+        assert_eq!(record.moves[1].comment.as_deref(), Some("takes nothing"));
+        assert_eq!(record.moves[4].comment.as_deref(), Some("the end is near"));
+        assert_eq!(record.moves[4].eval, None);
+        assert_eq!(
+            record.board().unwrap().key(),
+            board::Board::load("2320212422").unwrap().key()
+        );
+    }
+
+    /// Test whether games are written and read again.
+    #[test]
+    fn roundtrip() {
+        let record: Record = GAME.parse().unwrap();
+        let text = record.to_string();
+        assert_eq!(text, GAME);
+        assert_eq!(text.parse::<Record>().unwrap(), record);
+
+        // Long games are wrapped.
+        let board = board::Board::load("0123432100304022").unwrap();
+        let position = board::Position::load("0123432100304022").unwrap();
+        let mut record = Record::from_moves(board::Rules::default(), position.history()).unwrap();
+        record.rules = board::Rules::new(12, 9, true, 4).unwrap();
+        record.termination = Some(Termination::Forfeit);
+        for annotated in &mut record.moves {
+            annotated.comment = Some(String::from("a {braced} \\ comment"));
+        }
+
+        let text = record.to_string();
+        assert!(text.lines().all(|line| line.len() <= 80));
+        let read: Record = text.parse().unwrap();
+        assert_eq!(read.rules, record.rules);
+        assert_eq!(read.termination, Some(Termination::Forfeit));
+        assert_eq!(read.result, None);
+        assert_eq!(
+            read.moves[0].comment.as_deref(),
+            Some("a {braced} \\ comment")
+        );
+        assert_eq!(read.board().unwrap().key(), board.key());
+
+        // Only an evaluation at the start of the comment is read as one.
+        let mut record: Record = "1. 22 20 *".parse().unwrap();
+        record.moves[0].comment = Some(String::from("[%eval win 3] is wrong"));
+        record.moves[1].eval = Some(eval::Eval::from(eval::Result::Loss, 4));
+        record.moves[1].comment = Some(String::from("not [%eval draw 0]"));
+        let text = record.to_string();
+        assert!(text.contains("{\\[%eval win 3] is wrong}"));
+        assert_eq!(text.parse::<Record>().unwrap(), record);
+    }
+
+    /// Test whether multiple games are read from one text.
+    #[test]
+    fn multiple() {
+        let text = format!("{}\n{}\n1. 00 01 *\n", GAME, GAME);
+        let records = Record::parse_all(&text).unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[2].players, ["?", "?"]);
+        assert_eq!(records[2].result, None);
+        assert_eq!(records[2].moves.len(), 2);
+
+        assert!(text.parse::<Record>().is_err());
+        assert!(Record::parse_all("").unwrap().is_empty());
+    }
+
+    /// Test whether invalid records are refused.
+    #[test]
+    fn invalid() {
+        for text in [
+            "1. 00 01",
+            "[Player1 solver]\n*",
+            "[Player1 \"solver\"\n*",
+            "{comment} 1. 00 *",
+            "1. 00 55 *",
+            "1. 00 00 *",
+            "1. 00 01 [Event \"late\"] *",
+            "1. 00 {[%eval win 999]} *",
+            "[Result \"0-1\"]\n1. 00 *",
+            "1. 23 32 2. 20 02 3. 21 12 4. 24 42 5. 22 0-1",
+            "[Rules \"stones=3\"]\n*",
+            "[Termination \"boredom\"]\n*",
+            "[Termination \"full board\"]\n1. 23 32 2. 20 02 3. 21 12 4. 24 42 5. 22 1-0",
+            "[Termination \"forfeit\"]\n1. 23 32 2. 20 02 3. 21 12 4. 24 42 5. 22 1-0",
+        ] {
+            assert!(text.parse::<Record>().is_err(), "{}", text);
+        }
+    }
+
+    /// Test whether records are added to files and read again.
+    #[test]
+    fn files() {
+        let path = std::env::temp_dir().join(format!("katalon-record-{}.txt", std::process::id()));
+        std::fs::remove_file(&path).ok();
+
+        let record: Record = GAME.parse().unwrap();
+        append(&path, &record).unwrap();
+        append(&path, &record).unwrap();
+        let records = load(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(records, vec![record.clone(), record]);
+        assert!(load(&path).is_err());
+    }
+
+    /// Test the conversion of days to dates.
+    #[test]
+    fn dates() {
+        assert_eq!(civil(0), (1970, 1, 1));
+        assert_eq!(civil(11016), (2000, 2, 29));
+        assert_eq!(civil(20744), (2026, 10, 18));
+        assert_eq!(today().len(), 10);
+    }
+}
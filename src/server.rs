@@ -0,0 +1,499 @@
+use crate::{analysis, error, solver};
+use serde_json::{json, Value};
+use std::io::{BufRead, Read, Write};
+
+// The server answers HTTP GET requests with JSON, using the same requests and
+// responses as the analysis module, e.g.
+//   GET /eval?moves=0020&timeout_ms=5000
+//   GET /bestmoves?fen=...../...../...../...../.....+X+12,12+-+0+0
+//   GET /eval_all?moves=0020
+//   GET /pv?moves=0020&nodes=1000000
+//   GET /stats
+//
+// The path gives the op and the query the other fields of the request. The
+// searches share one table which lives as long as the server, and run one at
+// a time. Their time is limited by the timeout of the server, which is also
+// used if the request does not give one. Invalid requests are answered with
+// 400 Bad Request before waiting for the running search, and a search which
+// fails unexpectedly with 500 Internal Server Error. The stats of the server
+// are given by /stats.
+//
+// The connections are answered by a fixed number of workers. Connections
+// which do not fit in the queue of the workers are answered with 503 Service
+// Unavailable, as are requests which wait longer than their timeout for the
+// searches before them; the time a request waits counts against its timeout.
+// Requests of which the request line and headers exceed MAX_HEAD bytes are
+// answered with 431 Request Header Fields Too Large.
+
+/// An HTTP server analysing positions, meant to be used on localhost.
+pub struct Server {
+    listener: std::net::TcpListener,
+    session: std::sync::Arc<std::sync::Mutex<solver::Session>>,
+    /// The longest time a search may take.
+    timeout: std::time::Duration,
+}
+
+/// The time a client has to send its request and to receive the response.
+const IO_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+/// The number of connections answered at the same time.
+const WORKERS: usize = 4;
+/// The number of connections waiting for a worker.
+const QUEUE: usize = 16;
+/// The longest request line and headers read, in bytes.
+const MAX_HEAD: u64 = 8 * 1024;
+/// The time between the attempts to take the session of a running search.
+const POLL: std::time::Duration = std::time::Duration::from_millis(10);
+
+impl Server {
+    /// Listen on the address, searching with the table of the session for at
+    /// most the timeout per request.
+    pub fn bind(
+        address: impl std::net::ToSocketAddrs,
+        session: solver::Session,
+        timeout: std::time::Duration,
+    ) -> error::Result<Self> {
+        Ok(Self {
+            listener: std::net::TcpListener::bind(address)?,
+            session: std::sync::Arc::new(std::sync::Mutex::new(session)),
+            timeout,
+        })
+    }
+
+    /// The address the server listens on, useful when bound to port 0.
+    pub fn local_addr(&self) -> error::Result<std::net::SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Answer the connections forever with the workers.
+    pub fn run(&self) {
+        // The time a connection waits in the queue counts against its timeout.
+        type Connection = (std::net::TcpStream, std::time::Instant);
+        let (sender, receiver) = std::sync::mpsc::sync_channel::<Connection>(QUEUE);
+        let receiver = std::sync::Arc::new(std::sync::Mutex::new(receiver));
+
+        for _ in 0..WORKERS {
+            let receiver = receiver.clone();
+            let session = self.session.clone();
+            let timeout = self.timeout;
+            std::thread::spawn(move || loop {
+                let connection = receiver
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .recv();
+                let (stream, accepted) = match connection {
+                    Ok(connection) => connection,
+                    Err(_) => return,
+                };
+                // The client may have gone already, there is nobody to tell.
+                serve(stream, accepted, &session, timeout).ok();
+            });
+        }
+
+        for stream in self.listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(_) => continue,
+            };
+
+            let connection = (stream, std::time::Instant::now());
+            if let Err(std::sync::mpsc::TrySendError::Full((stream, _))) =
+                sender.try_send(connection)
+            {
+                respond(stream, busy()).ok();
+            }
+        }
+    }
+}
+
+/// Read a single request from the connection and answer it.
+fn serve(
+    stream: std::net::TcpStream,
+    accepted: std::time::Instant,
+    session: &std::sync::Mutex<solver::Session>,
+    timeout: std::time::Duration,
+) -> error::Result<()> {
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    let mut reader = std::io::BufReader::new(stream.try_clone()?.take(MAX_HEAD));
+    let answer = match read_request(&mut reader)? {
+        Some(line) => route(&line, session, accepted + timeout),
+        None => (
+            "431 Request Header Fields Too Large",
+            failure(
+                "request",
+                &format!(
+                    "The request line and headers should be shorter than {} bytes.",
+                    MAX_HEAD
+                ),
+            ),
+        ),
+    };
+    respond(stream, answer)
+}
+
+/// Read the request line, or None if the request line and the headers are
+/// longer than the reader allows.
+fn read_request<R: Read>(
+    reader: &mut std::io::BufReader<std::io::Take<R>>,
+) -> error::Result<Option<String>> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+
+    // The headers are not needed, but have to be read before answering.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && !header.trim().is_empty() {
+        header.clear();
+    }
+
+    match reader.get_ref().limit() {
+        0 => Ok(None),
+        _ => Ok(Some(line)),
+    }
+}
+
+/// Write the status and the body of the response to the connection.
+fn respond(
+    mut stream: std::net::TcpStream,
+    (status, body): (&'static str, Value),
+) -> error::Result<()> {
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    let body = body.to_string();
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// Answer the request line with the status and the body of the response,
+/// searching until the deadline at the latest.
+fn route(
+    line: &str,
+    session: &std::sync::Mutex<solver::Session>,
+    deadline: std::time::Instant,
+) -> (&'static str, Value) {
+    let (method, target) = match line.split_whitespace().collect::<Vec<_>>()[..] {
+        [method, target, _version] => (method, target),
+        _ => return bad_request(error::Error::Format(String::from("Invalid request line."))),
+    };
+    if method != "GET" {
+        return (
+            "405 Method Not Allowed",
+            failure("method", "Only GET requests are supported."),
+        );
+    }
+
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    // The request is checked before waiting for the search which may run.
+    match path {
+        "/eval" | "/bestmoves" | "/eval_all" | "/pv" => {
+            let now = std::time::Instant::now();
+            let left = deadline.saturating_duration_since(now);
+            let fields = match request(&path[1..], query, left) {
+                Ok(fields) => fields,
+                Err(error) => return bad_request(error),
+            };
+            // The request may ask for less time than the server has left.
+            let ms = fields["timeout_ms"].as_u64().unwrap_or_default();
+            let deadline = now + std::time::Duration::from_millis(ms);
+            let mut request = match analysis::Request::parse(&fields) {
+                Ok(request) => request,
+                Err(error) => return bad_request(error),
+            };
+
+            let mut session = match lock(session, deadline) {
+                Some(session) => session,
+                None => return busy(),
+            };
+            request.limit_time(deadline.saturating_duration_since(std::time::Instant::now()));
+
+            // A panicking search should not take the server down with it, the
+            // session takes its table back before the panic gets here.
+            let answer = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                request.answer(&mut session)
+            }));
+            match answer {
+                Ok(response) => ("200 OK", response),
+                Err(_) => (
+                    "500 Internal Server Error",
+                    failure("panic", "The search failed unexpectedly."),
+                ),
+            }
+        }
+        "/stats" => {
+            let mut session = match lock(session, deadline) {
+                Some(session) => session,
+                None => return busy(),
+            };
+            let response = json!({
+                "searches": session.searches,
+                "visited": session.visited,
+                "time_ms": session.time.as_millis() as u64,
+                "table": session.table_stats(),
+            });
+            ("200 OK", response)
+        }
+        _ => (
+            "404 Not Found",
+            failure(
+                "path",
+                &format!(
+                    "Unknown path {}, use /eval, /bestmoves, /eval_all, /pv or /stats.",
+                    path
+                ),
+            ),
+        ),
+    }
+}
+
+/// Build the analysis request from the op and the query, limiting the time of
+/// the search to the timeout.
+fn request(op: &str, query: &str, timeout: std::time::Duration) -> error::Result<Value> {
+    let mut request = json!({});
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let name = decode(name)?;
+        let value = decode(value)?;
+
+        request[name.as_str()] = match name.as_str() {
+            "timeout_ms" | "nodes" | "depth" => match value.parse::<u64>() {
+                Ok(number) => json!(number),
+                Err(_) => {
+                    return Err(error::Error::Format(format!(
+                        "The {} should be a positive integer.",
+                        name
+                    )))
+                }
+            },
+            _ => json!(value),
+        };
+    }
+
+    // The path decides the op, even if the query gives one as well.
+    request["op"] = json!(op);
+    let limit = timeout.as_millis() as u64;
+    let ms = request["timeout_ms"].as_u64().unwrap_or(limit).min(limit);
+    request["timeout_ms"] = json!(ms);
+    Ok(request)
+}
+
+/// Decode a component of the query, where + is a space and %XX a byte.
+fn decode(text: &str) -> error::Result<String> {
+    let invalid = || error::Error::Format(format!("Invalid query {}.", text));
+
+    let mut bytes = Vec::new();
+    let mut input = text.bytes();
+    while let Some(byte) = input.next() {
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex = [
+                    input.next().ok_or_else(invalid)?,
+                    input.next().ok_or_else(invalid)?,
+                ];
+                let hex = std::str::from_utf8(&hex).map_err(|_| invalid())?;
+                bytes.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+            }
+            byte => bytes.push(byte),
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| invalid())
+}
+
+/// Lock the session before the deadline, or None if the searches before
+/// take too long. The session stays usable even if a search panicked.
+fn lock(
+    session: &std::sync::Mutex<solver::Session>,
+    deadline: std::time::Instant,
+) -> Option<std::sync::MutexGuard<'_, solver::Session>> {
+    loop {
+        match session.try_lock() {
+            Ok(session) => return Some(session),
+            Err(std::sync::TryLockError::Poisoned(poisoned)) => return Some(poisoned.into_inner()),
+            Err(std::sync::TryLockError::WouldBlock) => {
+                if std::time::Instant::now() >= deadline {
+                    return None;
+                }
+                std::thread::sleep(POLL);
+            }
+        }
+    }
+}
+
+fn bad_request(error: error::Error) -> (&'static str, Value) {
+    (
+        "400 Bad Request",
+        analysis::respond(&Value::Null, Err(error), None),
+    )
+}
+
+fn busy() -> (&'static str, Value) {
+    (
+        "503 Service Unavailable",
+        failure(
+            "busy",
+            "The server is busy with other searches, try again later.",
+        ),
+    )
+}
+
+fn failure(kind: &str, message: &str) -> Value {
+    json!({ "error": { "kind": kind, "message": message } })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    /// Start a server on a free port of localhost.
+    fn start(timeout: std::time::Duration) -> std::net::SocketAddr {
+        let server = Server::bind("127.0.0.1:0", solver::Session::new(1_000_000), timeout).unwrap();
+        let address = server.local_addr().unwrap();
+        std::thread::spawn(move || server.run());
+        address
+    }
+
+    /// Send a request to the server and return the status code and the body.
+    fn get(address: std::net::SocketAddr, request: &str) -> (u16, Value) {
+        let mut stream = std::net::TcpStream::connect(address).unwrap();
+        write!(stream, "{}\r\nHost: localhost\r\n\r\n", request).unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    /// Test whether the endpoints answer like the analysis.
+    #[test]
+    fn endpoints() {
+        let address = start(std::time::Duration::from_secs(60));
+        let moves = "12104023441433032242301121";
+
+        let (status, response) = get(address, &format!("GET /eval?moves={} HTTP/1.1", moves));
+        assert_eq!(status, 200);
+        assert_eq!(response["eval"], json!({ "result": "win", "distance": 2 }));
+
+        let (status, response) = get(address, &format!("GET /pv?moves={} HTTP/1.1", moves));
+        assert_eq!(status, 200);
+        assert_eq!(response["moves"].as_array().unwrap().len(), 2);
+
+        let (_, response) = get(address, &format!("GET /eval_all?moves={} HTTP/1.1", moves));
+        assert!(response["moves"].as_array().unwrap().len() > 1);
+
+        let fen = "...../...../...../...../.....+X+12,12+-+0+0";
+        let (status, response) = get(
+            address,
+            &format!("GET /bestmoves?fen={}&nodes=1000 HTTP/1.1", fen),
+        );
+        assert_eq!(status, 200);
+        assert_eq!(response["error"]["kind"], "nodes");
+        assert_eq!(response["stats"]["visited"], 1000);
+
+        // The table is shared by all the searches.
+        let (status, response) = get(address, "GET /stats HTTP/1.1");
+        assert_eq!(status, 200);
+        assert_eq!(response["searches"], 4);
+        assert!(response["table"]["count"].as_u64().unwrap() > 0);
+        assert!(response["table"]["hits"].as_u64().unwrap() > 0);
+    }
+
+    /// Test whether invalid requests are answered with an error status.
+    #[test]
+    fn errors() {
+        let address = start(std::time::Duration::from_secs(60));
+
+        let (status, response) = get(address, "GET /eval?moves=5 HTTP/1.1");
+        assert_eq!(status, 400);
+        assert_eq!(response["error"]["kind"], "notation");
+
+        let (status, response) = get(address, "GET /eval?nodes=many HTTP/1.1");
+        assert_eq!(status, 400);
+        assert_eq!(response["error"]["kind"], "format");
+
+        let (status, _) = get(address, "GET /eval?fen=%ZZ HTTP/1.1");
+        assert_eq!(status, 400);
+
+        let (status, response) = get(address, "GET /jump HTTP/1.1");
+        assert_eq!(status, 404);
+        assert_eq!(response["error"]["kind"], "path");
+
+        let (status, _) = get(address, "POST /eval HTTP/1.1");
+        assert_eq!(status, 405);
+    }
+
+    /// Test whether searches are limited by the timeout of the server.
+    #[test]
+    fn timeout() {
+        let address = start(std::time::Duration::ZERO);
+
+        let (status, response) = get(address, "GET /eval?moves=0123&timeout_ms=60000 HTTP/1.1");
+        assert_eq!(status, 200);
+        assert_eq!(response["error"]["kind"], "timeout");
+        assert_eq!(response["stats"]["stop"], "timeout");
+    }
+
+    /// Test whether long request lines and headers are refused.
+    #[test]
+    fn head() {
+        let read = |head: String| {
+            let mut reader = std::io::BufReader::new(head.as_bytes().take(MAX_HEAD));
+            read_request(&mut reader).unwrap()
+        };
+
+        let line = "GET /eval HTTP/1.1\r\n";
+        assert_eq!(
+            read(format!("{}Host: localhost\r\n\r\n", line)).as_deref(),
+            Some(line)
+        );
+        assert_eq!(
+            read(format!(
+                "GET /eval?moves={} HTTP/1.1\r\n\r\n",
+                "0".repeat(10_000)
+            )),
+            None
+        );
+        assert_eq!(
+            read(format!("{}Cookie: {}\r\n\r\n", line, "a".repeat(10_000))),
+            None
+        );
+    }
+
+    /// Test whether waiting for the session is limited by the deadline.
+    #[test]
+    fn deadline() {
+        let session = std::sync::Mutex::new(solver::Session::new(1000));
+        let guard = session.lock().unwrap();
+        let deadline = std::time::Instant::now() + std::time::Duration::from_millis(30);
+        assert!(lock(&session, deadline).is_none());
+        assert!(std::time::Instant::now() >= deadline);
+
+        drop(guard);
+        assert!(lock(&session, deadline).is_some());
+    }
+
+    /// Test whether the query is decoded.
+    #[test]
+    fn query() {
+        assert_eq!(decode("a+b%2C%2fc").unwrap(), "a b,/c");
+        assert!(decode("%2").is_err());
+        assert!(decode("%zz").is_err());
+
+        let timeout = std::time::Duration::from_secs(10);
+        assert_eq!(
+            request("pv", "moves=0020&timeout_ms=600", timeout).unwrap(),
+            json!({ "op": "pv", "moves": "0020", "timeout_ms": 600 })
+        );
+        assert_eq!(
+            request("eval", "timeout_ms=60000", timeout).unwrap()["timeout_ms"],
+            10000
+        );
+        assert_eq!(request("eval", "", timeout).unwrap()["timeout_ms"], 10000);
+    }
+}
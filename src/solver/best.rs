@@ -1,91 +1,157 @@
-use super::negamax;
-use crate::{board, eval, stats::search};
+use super::{eval::mtdf, limits, negamax};
+use crate::{board, error, eval, stats::search};
 
-/// Return all of the best moves if finished within the specified time with stats.
+/// Return all of the best moves if finished within the specified limits with stats.
 pub fn bestmoves_with_stats(
     node: &board::Board,
-    timeout: std::time::Duration,
-) -> (Result<(eval::Eval, Vec<(u8, u8)>), ()>, search::Stats) {
-    let (send_timeout, recv_timeout) = std::sync::mpsc::channel();
-    let (send_result, recv_result) = std::sync::mpsc::channel();
-
-    let node_clone = node.clone();
-
-    std::thread::spawn(move || {
-        send_result
-            .send(best(&node_clone, recv_timeout))
-            .expect("Could not send result.");
-    });
-
-    match recv_result.recv_timeout(timeout) {
-        Ok(result) => result, // this should always be Ok
-        Err(_) => {
-            send_timeout.send(()).expect("Could not send timeout.");
-            recv_result
-                .recv()
-                .expect("Could not wait until thread terminated.")
-        }
+    limits: impl Into<limits::SearchLimits>,
+) -> (error::Result<(eval::Eval, Vec<board::Move>)>, search::Stats) {
+    if node.isover().is_some() {
+        return (
+            Err(error::Error::Move(board::MoveError::GameOver)),
+            search::Stats::new(),
+        );
+    }
+
+    let mut negamax = negamax::Negamax::new(limits.into(), node.movecount(), node.onturn());
+    let result = negamax.run(node, best);
+    (result, negamax.stats)
+}
+
+/// Return all of the best moves if finished within the specified limits with
+/// stats, and report the progress to the callback about every interval.
+pub fn bestmoves_with_progress(
+    node: &board::Board,
+    limits: impl Into<limits::SearchLimits>,
+    interval: std::time::Duration,
+    callback: impl FnMut(&search::Progress),
+) -> (error::Result<(eval::Eval, Vec<board::Move>)>, search::Stats) {
+    if node.isover().is_some() {
+        return (
+            Err(error::Error::Move(board::MoveError::GameOver)),
+            search::Stats::new(),
+        );
     }
+
+    let mut negamax = negamax::Negamax::new(limits.into(), node.movecount(), node.onturn())
+        .with_progress(interval, callback);
+    let result = negamax.run(node, best);
+    (result, negamax.stats)
 }
 
-/// Return all of the best moves if finished within the specified time.
-pub fn bestmoves(node: &board::Board, timeout: std::time::Duration) -> Result<Vec<(u8, u8)>, ()> {
+/// Return all of the best moves if finished within the specified limits.
+pub fn bestmoves(
+    node: &board::Board,
+    limits: impl Into<limits::SearchLimits>,
+) -> error::Result<Vec<board::Move>> {
+    if node.isover().is_some() {
+        return Err(error::Error::Move(board::MoveError::GameOver));
+    }
+
     // If there is only one possible move we immediately return this move.
     let moves = node.moves();
     if moves.len() == 1 {
         return Ok(moves);
     }
 
-    let (_eval, moves) = bestmoves_with_stats(node, timeout).0?;
+    let (_eval, moves) = bestmoves_with_stats(node, limits).0?;
     Ok(moves)
 }
 
-fn best(
-    node: &board::Board,
-    recv_timeout: std::sync::mpsc::Receiver<()>,
-) -> (Result<(eval::Eval, Vec<(u8, u8)>), ()>, search::Stats) {
-    let now = std::time::Instant::now();
-
-    let mut negamax = negamax::Negamax::new(recv_timeout, node.movecount(), node.onturn());
-    let mut bestmoves: Vec<(u8, u8)> = Vec::new();
+/// Search the best moves within an ongoing search, the evaluation is from the
+/// perspective of the player onturn.
+pub(super) fn best(
+    node: &mut board::Board,
+    negamax: &mut negamax::Negamax,
+) -> error::Result<(eval::Eval, Vec<board::Move>)> {
+    let mut bestmoves: Vec<board::Move> = Vec::new();
     let mut max = eval::Eval::MIN;
 
-    let mut moves = node.moves();
-    moves.sort_by(|(_s1, c1), (_s2, _c2)| match node.isfull(*c1) {
-        true => std::cmp::Ordering::Greater,
-        false => std::cmp::Ordering::Less,
-    });
-
     // TODO IMPORTANT add a form of the MTD(f) search
-    for &(square, cell) in &moves {
-        let mut child = node.clone();
-        child.play(square, cell);
+    for mv in node.ordered() {
+        let undo = node.play(mv.square, mv.cell);
 
         let alpha = eval::Eval::MIN;
         let beta = eval::Eval::MAX;
 
         // TODO reuse improved alpha (beta does not change here)
-        let value = negamax::eval(&child, alpha, beta, &mut negamax);
-
-        if value.is_err() {
-            negamax.stats.table = negamax.table.stats();
-            negamax.stats.time = now.elapsed();
+        let value = negamax::eval(node, alpha, beta, negamax);
+        node.unplay(undo);
 
-            return (Err(()), negamax.stats);
-        }
-
-        let value = -value.unwrap();
+        let value = -value?;
         if value > max {
             max = value;
             bestmoves.clear();
-            bestmoves.push((square, cell));
+            bestmoves.push(mv);
         } else if value == max {
-            bestmoves.push((square, cell));
+            bestmoves.push(mv);
         }
+        negamax.window.0 = max;
     }
 
-    negamax.stats.table = negamax.table.stats();
-    negamax.stats.time = now.elapsed();
+    Ok((max, bestmoves))
+}
+
+/// Return the evaluation and the principal variation, a line of best moves
+/// until the end of the game, if finished within the specified limits with stats.
+pub fn pv_with_stats(
+    node: &board::Board,
+    limits: impl Into<limits::SearchLimits>,
+) -> (error::Result<(eval::Eval, Vec<board::Move>)>, search::Stats) {
+    if node.isover().is_some() {
+        return (
+            Err(error::Error::Move(board::MoveError::GameOver)),
+            search::Stats::new(),
+        );
+    }
+
+    let mut negamax = negamax::Negamax::new(limits.into(), node.movecount(), node.onturn());
+    let result = negamax.run(node, line);
+    (result, negamax.stats)
+}
+
+/// Return the evaluation and the principal variation if finished within the
+/// specified limits.
+pub fn pv(
+    node: &board::Board,
+    limits: impl Into<limits::SearchLimits>,
+) -> error::Result<(eval::Eval, Vec<board::Move>)> {
+    pv_with_stats(node, limits).0
+}
+
+/// Follow the moves which keep the value of the root until the game is over.
+/// All the values are searched in one search, so most come from the table.
+/// The value of a node should always be the value of one of its children, if
+/// none is found the line stops there rather than giving a wrong move.
+pub(super) fn line(
+    node: &mut board::Board,
+    negamax: &mut negamax::Negamax,
+) -> error::Result<(eval::Eval, Vec<board::Move>)> {
+    let root = mtdf(node, negamax)?;
+    let mut value = root;
+    let mut moves = Vec::new();
+
+    while node.isover().is_none() {
+        let mut next = None;
+        for mv in node.moves() {
+            let undo = node.play(mv.square, mv.cell);
+            let child = mtdf(node, negamax);
+            node.unplay(undo);
+
+            if -child? == value {
+                next = Some(mv);
+                break;
+            }
+        }
+
+        let mv = match next {
+            Some(mv) => mv,
+            None => break,
+        };
+        node.play(mv.square, mv.cell);
+        moves.push(mv);
+        value = -value;
+    }
 
-    (Ok((max, bestmoves)), negamax.stats)
+    Ok((root, moves))
 }
@@ -1,94 +1,119 @@
-use super::negamax;
-use crate::{board, eval, stats::search};
+use super::{limits, negamax};
+use crate::{board, error, eval, stats::search};
 
-/// Evaluate all the possible moves from the current board position.
-// TODO pub fn eval_all()
-
-/// Evaluate the current board position and provide stats.
-pub fn eval_with_stats(
+/// Evaluate all the possible moves from the current board position within the
+/// given limits and provide stats. The evaluations are from the perspective of
+/// the player onturn, the moves are only given once per symmetry.
+pub fn eval_all_with_stats(
     node: &board::Board,
-    timeout: std::time::Duration,
-) -> (Result<eval::Eval, ()>, search::Stats) {
-    let (send_timeout, recv_timeout) = std::sync::mpsc::channel();
-    let (send_result, recv_result) = std::sync::mpsc::channel();
-
-    let node_clone = node.clone();
+    limits: impl Into<limits::SearchLimits>,
+) -> (error::Result<Vec<(board::Move, eval::Eval)>>, search::Stats) {
+    if node.isover().is_some() {
+        return (
+            Err(error::Error::Move(board::MoveError::GameOver)),
+            search::Stats::new(),
+        );
+    }
 
-    std::thread::spawn(move || {
-        send_result
-            .send(eval_mtdf(&node_clone, recv_timeout))
-            .expect("Could not send result.");
-    });
+    let mut negamax = negamax::Negamax::new(limits.into(), node.movecount(), node.onturn());
+    let result = negamax.run(node, all);
+    (result, negamax.stats)
+}
 
-    match recv_result.recv_timeout(timeout) {
-        Ok(result) => result, // this should always be Ok
-        Err(_) => {
-            send_timeout.send(()).expect("Could not send timeout.");
-            recv_result
-                .recv()
-                .expect("Could not wait until thread terminated.")
-        }
+/// Evaluate all the moves within one search, so they share the table as
+/// their subtrees overlap.
+pub(super) fn all(
+    node: &mut board::Board,
+    negamax: &mut negamax::Negamax,
+) -> error::Result<Vec<(board::Move, eval::Eval)>> {
+    let mut evaluations = Vec::new();
+    for mv in node.moves() {
+        let undo = node.play(mv.square, mv.cell);
+        let value = mtdf(node, negamax);
+        node.unplay(undo);
+
+        evaluations.push((mv, -value?));
     }
+    Ok(evaluations)
 }
 
-/// Evaluate the current board position.
-pub fn eval(node: &board::Board, timeout: std::time::Duration) -> Result<eval::Eval, ()> {
-    eval_with_stats(node, timeout).0
+/// Evaluate all the possible moves from the current board position within the
+/// given limits.
+pub fn eval_all(
+    node: &board::Board,
+    limits: impl Into<limits::SearchLimits>,
+) -> error::Result<Vec<(board::Move, eval::Eval)>> {
+    eval_all_with_stats(node, limits).0
 }
 
-/// Evaluate the current board position just with negamax.
-#[allow(dead_code)]
-fn eval_plain(
+/// Evaluate the current board position within the given limits and provide stats.
+pub fn eval_with_stats(
     node: &board::Board,
-    recv_timeout: std::sync::mpsc::Receiver<()>,
-) -> (Result<eval::Eval, ()>, search::Stats) {
-    let mut negamax = negamax::Negamax::new(recv_timeout, node.movecount(), node.onturn());
-
-    negamax.stats.stopwatch_start();
+    limits: impl Into<limits::SearchLimits>,
+) -> (error::Result<eval::Eval>, search::Stats) {
+    let negamax = negamax::Negamax::new(limits.into(), node.movecount(), node.onturn());
+    eval_mtdf(node, negamax)
+}
 
-    let alpha = eval::Eval::MIN;
-    let beta = eval::Eval::MAX;
-    let result = negamax::eval(&node, alpha, beta, &mut negamax);
+/// Evaluate the current board position within the given limits, report the
+/// progress to the callback about every interval and provide stats.
+pub fn eval_with_progress(
+    node: &board::Board,
+    limits: impl Into<limits::SearchLimits>,
+    interval: std::time::Duration,
+    callback: impl FnMut(&search::Progress),
+) -> (error::Result<eval::Eval>, search::Stats) {
+    let negamax = negamax::Negamax::new(limits.into(), node.movecount(), node.onturn())
+        .with_progress(interval, callback);
+    eval_mtdf(node, negamax)
+}
 
-    negamax.stats.stopwatch_stop();
-    negamax.stats.add_table(negamax.table.stats());
+/// Evaluate the current board position within the given limits.
+pub fn eval(
+    node: &board::Board,
+    limits: impl Into<limits::SearchLimits>,
+) -> error::Result<eval::Eval> {
+    eval_with_stats(node, limits).0
+}
 
-    match result {
-        Ok(result) => (Ok(result), negamax.stats),
-        Err(_) => {
-            negamax.stats.timeout();
-            (Err(()), negamax.stats)
-        }
-    }
+/// Evaluate the current board position just with negamax.
+#[allow(dead_code)]
+fn eval_plain(
+    node: &board::Board,
+    mut negamax: negamax::Negamax,
+) -> (error::Result<eval::Eval>, search::Stats) {
+    let result = negamax.run(node, |node, negamax| {
+        negamax::eval(node, eval::Eval::MIN, eval::Eval::MAX, negamax)
+    });
+    (result, negamax.stats)
 }
 
 /// Evaluate the current board position using MTD(f).
-#[allow(dead_code)]
 fn eval_mtdf(
     node: &board::Board,
-    recv_timeout: std::sync::mpsc::Receiver<()>,
-) -> (Result<eval::Eval, ()>, search::Stats) {
-    let mut negamax = negamax::Negamax::new(recv_timeout, node.movecount(), node.onturn());
+    mut negamax: negamax::Negamax,
+) -> (error::Result<eval::Eval>, search::Stats) {
+    let result = negamax.run(node, mtdf);
+    (result, negamax.stats)
+}
 
+/// Search the value of the board with MTD(f) within an ongoing search, the
+/// value is from the perspective of the player onturn.
+pub(super) fn mtdf(
+    node: &mut board::Board,
+    negamax: &mut negamax::Negamax,
+) -> error::Result<eval::Eval> {
     let mut max = eval::Eval::MAX.raw();
     let mut min = eval::Eval::MIN.raw();
     let mut guess = 0;
 
-    negamax.stats.stopwatch_start();
-
     while min < max {
         negamax.stats.nullwindows += 1;
+        negamax.window = (eval::Eval::new(min), eval::Eval::new(max));
         let beta = std::cmp::max(guess, min + 1);
         let alpha = eval::Eval::new(beta - 1);
 
-        let result = negamax::eval(&node, alpha, eval::Eval::new(beta), &mut negamax);
-        if result.is_err() {
-            negamax.stats.stopwatch_stop();
-            negamax.stats.add_table(negamax.table.stats());
-            negamax.stats.timeout();
-            return (Err(()), negamax.stats);
-        }
-        guess = result.unwrap().raw();
+        guess = negamax::eval(node, alpha, eval::Eval::new(beta), negamax)?.raw();
 
         if guess < beta {
             max = guess;
@@ -97,53 +122,40 @@ fn eval_mtdf(
         }
     }
 
-    negamax.stats.stopwatch_stop();
-    negamax.stats.add_table(negamax.table.stats());
-
-    (Ok(eval::Eval::new(guess)), negamax.stats)
+    Ok(eval::Eval::new(guess))
 }
 
 /// Evaluate the current board position using a binary search with null windows.
 #[allow(dead_code)]
 fn eval_divide(
     node: &board::Board,
-    recv_timeout: std::sync::mpsc::Receiver<()>,
-) -> (Result<eval::Eval, ()>, search::Stats) {
-    let mut negamax = negamax::Negamax::new(recv_timeout, node.movecount(), node.onturn());
+    mut negamax: negamax::Negamax,
+) -> (error::Result<eval::Eval>, search::Stats) {
+    let result = negamax.run(node, divide);
+    (result, negamax.stats)
+}
 
+/// Search the value of the board with a binary search with null windows
+/// within an ongoing search.
+fn divide(node: &mut board::Board, negamax: &mut negamax::Negamax) -> error::Result<eval::Eval> {
     let mut max = eval::Eval::MAX.raw();
     let mut min = eval::Eval::MIN.raw();
-    let mut mid;
-
-    negamax.stats.stopwatch_start();
 
     loop {
         negamax.stats.nullwindows += 1;
-        mid = (min + max) / 2;
+        negamax.window = (eval::Eval::new(min), eval::Eval::new(max));
+        let mid = (min + max) / 2;
 
         let alpha = eval::Eval::new(mid - 1);
         let beta = eval::Eval::new(mid + 1);
 
-        let result = negamax::eval(&node, alpha, beta, &mut negamax);
-        if result.is_err() {
-            negamax.stats.stopwatch_stop();
-            negamax.stats.add_table(negamax.table.stats());
-            negamax.stats.timeout();
-            return (Err(()), negamax.stats);
-        }
-        let result = result.unwrap().raw();
-
+        let result = negamax::eval(node, alpha, beta, negamax)?.raw();
         if mid == result {
-            break;
+            return Ok(eval::Eval::new(mid));
         } else if result < mid {
             max = result;
         } else {
             min = result;
         }
     }
-
-    negamax.stats.stopwatch_stop();
-    negamax.stats.add_table(negamax.table.stats());
-
-    (Ok(eval::Eval::new(mid)), negamax.stats)
 }
@@ -0,0 +1,106 @@
+/// A handle with which a running search can be stopped from another thread.
+/// All the clones of a handle stop the same searches.
+#[derive(Debug, Clone, Default)]
+pub struct StopHandle {
+    stopped: std::sync::Arc<std::sync::atomic::AtomicBool>,
+}
+
+impl StopHandle {
+    /// Create a new handle which has not been stopped yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stop all the searches using this handle as soon as possible.
+    pub fn stop(&self) {
+        self.stopped
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    /// Return whether the handle has been stopped.
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(std::sync::atomic::Ordering::Relaxed)
+    }
+}
+
+/// The limits of a search, the search stops as soon as one of them is reached.
+/// By default there are no limits at all.
+#[derive(Debug, Clone, Default)]
+pub struct SearchLimits {
+    /// The maximum amount of time the search may take.
+    pub time: Option<std::time::Duration>,
+    /// The maximum number of states which may be visited.
+    pub nodes: Option<usize>,
+    /// The maximum number of moves the search may look ahead. As the solver
+    /// only knows exact results, the search stops if it has to look further.
+    pub depth: Option<i16>,
+    /// A handle to stop the search from another thread.
+    pub stop: Option<StopHandle>,
+}
+
+impl SearchLimits {
+    /// Create limits without any limit.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit the amount of time the search may take.
+    pub fn time(mut self, time: std::time::Duration) -> Self {
+        self.time = Some(time);
+        self
+    }
+
+    /// Limit the number of states the search may visit.
+    pub fn nodes(mut self, nodes: usize) -> Self {
+        self.nodes = Some(nodes);
+        self
+    }
+
+    /// Limit the number of moves the search may look ahead.
+    pub fn depth(mut self, depth: i16) -> Self {
+        self.depth = Some(depth);
+        self
+    }
+
+    /// Stop the search when the given handle is stopped.
+    pub fn stop(mut self, stop: StopHandle) -> Self {
+        self.stop = Some(stop);
+        self
+    }
+}
+
+impl From<std::time::Duration> for SearchLimits {
+    fn from(time: std::time::Duration) -> Self {
+        Self::new().time(time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test whether all the clones of a handle are stopped together.
+    #[test]
+    fn stop_handle() {
+        let handle = StopHandle::new();
+        let clone = handle.clone();
+        assert!(!handle.is_stopped());
+
+        std::thread::spawn(move || clone.stop()).join().unwrap();
+        assert!(handle.is_stopped());
+        assert!(!StopHandle::new().is_stopped());
+    }
+
+    /// Test building limits.
+    #[test]
+    fn search_limits() {
+        let limits = SearchLimits::new().nodes(100).depth(10);
+        assert_eq!(limits.time, None);
+        assert_eq!(limits.nodes, Some(100));
+        assert_eq!(limits.depth, Some(10));
+        assert!(limits.stop.is_none());
+
+        let limits = SearchLimits::from(std::time::Duration::from_secs(1));
+        assert_eq!(limits.time, Some(std::time::Duration::from_secs(1)));
+    }
+}
@@ -3,28 +3,58 @@ use rand::Rng;
 
 mod best;
 mod eval;
+mod limits;
 mod negamax;
+mod session;
 mod table;
 
 // Make these function visible as e.g. solver::bestmoves.
-pub use best::{bestmoves, bestmoves_with_stats};
-pub use eval::{eval, eval_with_stats}; //, eval_all};
+// Every call of these functions allocates a new table of 1GB, which takes a
+// noticeable time for short searches. Use a Session to search multiple
+// positions with the same table.
+pub use best::{bestmoves, bestmoves_with_progress, bestmoves_with_stats, pv, pv_with_stats};
+pub use eval::{eval, eval_all, eval_all_with_stats, eval_with_progress, eval_with_stats};
+pub use limits::{SearchLimits, StopHandle};
+pub use session::Session;
 
 /// A player directed by the negamax algorithm.
 pub struct Solver;
 
 impl player::Player for Solver {
-    fn play(&self, node: &board::Board) -> (u8, u8) {
+    fn play(&self, node: &board::Board) -> board::Move {
         let bestmoves = bestmoves(node, std::time::Duration::MAX).unwrap();
         let mut rng = rand::thread_rng();
         bestmoves[rng.gen_range(0..bestmoves.len()) as usize]
     }
+
+    fn name(&self) -> String {
+        String::from("solver")
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::eval;
+    use crate::{error, eval, stats::search};
+
+    /// Evaluate within the limits with a small table, allocating the default
+    /// table takes too long.
+    fn solve_with(
+        board: &board::Board,
+        limits: SearchLimits,
+    ) -> (error::Result<eval::Eval>, search::Stats) {
+        let mut board = board.clone();
+        let table = table::Table::new(1_000_000);
+        let mut negamax =
+            negamax::Negamax::with_table(limits, board.movecount(), board.onturn(), table);
+        let result = negamax::eval(&mut board, eval::Eval::MIN, eval::Eval::MAX, &mut negamax);
+        (result, negamax.stats)
+    }
+
+    /// Evaluate without limits.
+    fn solve(board: &board::Board) -> eval::Eval {
+        solve_with(board, SearchLimits::new()).0.unwrap()
+    }
 
     /// Test if negamax detects its gonna be a draw.
     #[test]
@@ -44,4 +74,226 @@ mod tests {
             eval::Eval::from(eval::Result::Draw, 1)
         );
     }
+
+    /// Test if negamax uses the rules of the board, here a lower takestreak limit.
+    #[test]
+    fn rules() {
+        let rules = board::Rules::new(12, 9, false, 3).unwrap();
+        let moves = String::from("20033102212432011410302234201") + "211032";
+
+        let board = board::Board::load_with_rules(&moves, rules).unwrap();
+        assert_eq!(solve(&board), eval::Eval::from(eval::Result::Draw, 1));
+    }
+
+    /// Test the errors of the search.
+    #[test]
+    fn errors() {
+        let board = board::Board::load("2320212422").unwrap();
+        assert!(matches!(
+            bestmoves(&board, std::time::Duration::MAX),
+            Err(error::Error::Move(board::MoveError::GameOver))
+        ));
+
+        // The time limit is reached before the search even started.
+        let board = board::Board::load("0123").unwrap();
+        let limits = SearchLimits::new().time(std::time::Duration::ZERO);
+        let (result, stats) = solve_with(&board, limits);
+        assert!(matches!(result, Err(error::Error::Timeout)));
+        assert_eq!(stats.stop, Some(search::Stop::Timeout));
+
+        // The search is stopped before it started.
+        let stop = StopHandle::new();
+        stop.stop();
+        let (result, stats) = solve_with(&board, SearchLimits::new().stop(stop));
+        assert!(matches!(result, Err(error::Error::Cancelled)));
+        assert_eq!(stats.stop, Some(search::Stop::Stopped));
+    }
+
+    /// Test whether the search stops at the node and depth limits.
+    #[test]
+    fn limits() {
+        let board = board::Board::load("0123").unwrap();
+        let (result, stats) = solve_with(&board, SearchLimits::new().nodes(1000));
+        assert!(matches!(result, Err(error::Error::NodeLimit)));
+        assert_eq!(stats.stop, Some(search::Stop::Nodes));
+        assert_eq!(stats.visited, 1000);
+
+        let board = board::Board::load("12104023441433032242301121").unwrap();
+        let (result, stats) = solve_with(&board, SearchLimits::new().depth(1));
+        assert!(matches!(result, Err(error::Error::DepthLimit)));
+        assert_eq!(stats.stop, Some(search::Stop::Depth));
+
+        let (result, stats) = solve_with(&board, SearchLimits::new().depth(30).nodes(1_000_000));
+        assert_eq!(result.unwrap(), eval::Eval::from(eval::Result::Win, 2));
+        assert_eq!(stats.stop, None);
+    }
+
+    /// Test whether a search can be stopped from another thread.
+    #[test]
+    fn stop() {
+        let stop = StopHandle::new();
+        let handle = stop.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            handle.stop();
+        });
+
+        let board = board::Board::load("0123").unwrap();
+        let (result, stats) = solve_with(&board, SearchLimits::new().stop(stop));
+        assert!(matches!(result, Err(error::Error::Cancelled)));
+        assert_eq!(stats.stop, Some(search::Stop::Stopped));
+    }
+
+    /// Test whether the progress is reported while searching.
+    #[test]
+    fn progress() {
+        let mut board = board::Board::load("0123").unwrap();
+        let mut reports = Vec::new();
+        let table = table::Table::new(100_000);
+        let limits = SearchLimits::new().nodes(10_000);
+        let mut negamax =
+            negamax::Negamax::with_table(limits, board.movecount(), board.onturn(), table)
+                .with_progress(std::time::Duration::ZERO, |progress| {
+                    reports.push(*progress)
+                });
+        negamax.window = (eval::Eval::from(eval::Result::Loss, 10), eval::Eval::MAX);
+
+        let result = negamax::eval(&mut board, eval::Eval::MIN, eval::Eval::MAX, &mut negamax);
+        assert!(matches!(result, Err(error::Error::NodeLimit)));
+        let last = negamax.progress();
+        drop(negamax);
+
+        assert_eq!(reports.len(), 10);
+        assert!(reports
+            .windows(2)
+            .all(|pair| pair[0].visited < pair[1].visited));
+        assert_eq!(reports[0].min, eval::Eval::from(eval::Result::Loss, 10));
+        assert_eq!(last.visited, 10_000);
+        assert!(last.table_count > 0 && last.table_count <= last.table_size);
+    }
+
+    /// Test whether the stats of a search add up.
+    #[test]
+    fn stats() {
+        let board = board::Board::load("11243022031441013342232244").unwrap();
+        let (result, stats) = solve_with(&board, SearchLimits::new());
+        assert_eq!(result.unwrap(), eval::Eval::from(eval::Result::Win, 10));
+
+        assert_eq!(stats.depths[0], 1);
+        assert_eq!(stats.depths.iter().sum::<usize>(), stats.visited);
+        assert_eq!(stats.children, stats.visited - 1);
+        assert!(stats.first_cutoffs <= stats.cutoffs && stats.cutoffs <= stats.expanded);
+        assert!(stats.average_children() > 1.0);
+        let depth = stats.depths.len() as i32 - 1;
+        let branching = stats.effective_branching_factor();
+        assert!(branching > 1.0);
+        assert!((branching.powi(depth) / stats.visited as f64 - 1.0).abs() < 1e-6);
+
+        let fields = search::Stats::csv_header().split(',').count();
+        assert_eq!(stats.to_csv().split(',').count(), fields);
+    }
+
+    /// Test whether all the moves are evaluated like separate searches.
+    #[test]
+    fn all_moves() {
+        let mut board = board::Board::load("11243022031441013342232244").unwrap();
+        let table = table::Table::new(1_000_000);
+        let mut negamax = negamax::Negamax::with_table(
+            SearchLimits::new(),
+            board.movecount(),
+            board.onturn(),
+            table,
+        );
+
+        let evaluations = super::eval::all(&mut board, &mut negamax).unwrap();
+        assert_eq!(evaluations.len(), board.moves().len());
+        for &(mv, value) in evaluations.iter() {
+            let mut child = board.clone();
+            child.play(mv.square, mv.cell);
+            // The distance of a separate search is counted from the child.
+            assert_eq!(value.result(), (-solve(&child)).result(), "{}", mv);
+        }
+
+        let best = evaluations.iter().map(|&(_, value)| value).max().unwrap();
+        assert_eq!(best, solve(&board));
+    }
+
+    /// Test whether the principal variation keeps the value until the end.
+    #[test]
+    fn principal_variation() {
+        for (moves, result, distance) in [
+            ("12104023441433032242301121", eval::Result::Win, 2),
+            ("11243022031441013342232244", eval::Result::Win, 10),
+        ] {
+            let mut board = board::Board::load(moves).unwrap();
+            let table = table::Table::new(1_000_000);
+            let mut negamax = negamax::Negamax::with_table(
+                SearchLimits::new(),
+                board.movecount(),
+                board.onturn(),
+                table,
+            );
+            let onturn = board.onturn();
+
+            let (value, line) = best::line(&mut board.clone(), &mut negamax).unwrap();
+            assert_eq!(value, eval::Eval::from(result, distance));
+            assert_eq!(line.len(), distance as usize);
+
+            for mv in line {
+                board.play(mv.square, mv.cell);
+            }
+            assert_eq!(board.isover(), Some(board::Result::from_player(&onturn)));
+        }
+    }
+
+    /// Test if the evaluation is the same for all the symmetries of a board.
+    #[test]
+    fn symmetries() {
+        let positions = [
+            ("23013433224104422411223002", eval::Result::Draw, 18),
+            ("12104023441433032242301121", eval::Result::Win, 2),
+            ("11243022031441013342232244", eval::Result::Win, 10),
+        ];
+
+        for (moves, result, distance) in positions.iter() {
+            let board = board::Board::load(moves).unwrap();
+            let expected = eval::Eval::from(*result, *distance);
+
+            assert_eq!(solve(&board), expected);
+            for symmetry in board::Symmetry::ALL {
+                assert_eq!(
+                    solve(&board.transform(symmetry)),
+                    expected,
+                    "{} under {:?}",
+                    moves,
+                    symmetry
+                );
+            }
+        }
+    }
+
+    /// Test the evaluation against the benchmark results.
+    #[test]
+    fn benchmark() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/res/benchmark/depth25.txt");
+        let benchmark = std::fs::read_to_string(path).unwrap();
+
+        for line in benchmark.lines().take(50) {
+            let fields: Vec<&str> = line.split(' ').collect();
+            let board = board::Board::load(fields[0]).unwrap();
+            let result = match fields[1] {
+                "win" => eval::Result::Win,
+                "loss" => eval::Result::Loss,
+                _ => eval::Result::Draw,
+            };
+            let distance = fields[2].parse().unwrap();
+
+            assert_eq!(
+                solve(&board),
+                eval::Eval::from(result, distance),
+                "{}",
+                line
+            );
+        }
+    }
 }
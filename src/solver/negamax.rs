@@ -1,10 +1,31 @@
-use super::table;
-use crate::{board, eval, player, stats::search};
+use super::{limits, table};
+use crate::{board, error, eval, player, stats::search};
+
+/// The number of visited states after which the clock is checked again.
+const CLOCK_INTERVAL: usize = 1024;
+
+/// A callback to which the progress of the search is reported.
+struct Reporter<'a> {
+    callback: Box<dyn FnMut(&search::Progress) + 'a>,
+    /// The time between two reports.
+    interval: std::time::Duration,
+    /// The moment of the last report.
+    last: std::time::Instant,
+}
 
 /// Various variables needed during the negamax search.
-pub struct Negamax {
-    /// A receiving thread for when the timeout is reached.
-    recv_timeout: std::sync::mpsc::Receiver<()>,
+pub struct Negamax<'a> {
+    /// The limits after which the search stops.
+    limits: limits::SearchLimits,
+    /// The moment the search started.
+    start: std::time::Instant,
+    /// The moment the time limit is reached, if there is one.
+    deadline: Option<std::time::Instant>,
+    /// The callback to report the progress to, if any.
+    reporter: Option<Reporter<'a>>,
+    /// The lowest and highest value the evaluation of the root can still have,
+    /// kept up to date by the caller for the progress reports.
+    pub window: (eval::Eval, eval::Eval),
     /// The movecount when the search did not start yet.
     rootcount: i16,
     /// The player who is on turn when the search starts.
@@ -15,22 +36,139 @@ pub struct Negamax {
     pub stats: search::Stats,
 }
 
-impl Negamax {
+impl<'a> Negamax<'a> {
     /// Create new variables needed for a fresh search.
-    pub fn new(
-        recv_timeout: std::sync::mpsc::Receiver<()>,
+    pub fn new(limits: limits::SearchLimits, rootcount: i16, rootplayer: player::Players) -> Self {
+        // TODO make gb adaptive to movecount and available ram
+        Self::with_table(limits, rootcount, rootplayer, table::Table::from_gb(1.0))
+    }
+
+    /// Create new variables needed for a fresh search using the given table.
+    pub fn with_table(
+        limits: limits::SearchLimits,
         rootcount: i16,
         rootplayer: player::Players,
+        table: table::Table,
     ) -> Self {
+        // A time limit too large to be represented is no limit at all.
+        let start = std::time::Instant::now();
+        let deadline = limits.time.and_then(|time| start.checked_add(time));
+
         Self {
-            recv_timeout,
+            limits,
+            start,
+            deadline,
+            reporter: None,
+            window: (eval::Eval::MIN, eval::Eval::MAX),
             rootcount,
             rootplayer,
-            // TODO make gb adaptive to movecount and available ram
-            table: table::Table::from_gb(1.0),
+            table,
             stats: search::Stats::new(),
         }
     }
+
+    /// Report the progress of the search to the callback about every interval.
+    pub fn with_progress(
+        mut self,
+        interval: std::time::Duration,
+        callback: impl FnMut(&search::Progress) + 'a,
+    ) -> Self {
+        self.reporter = Some(Reporter {
+            callback: Box::new(callback),
+            interval,
+            last: self.start,
+        });
+        self
+    }
+
+    /// Run the search on a copy of the board, timing it and collecting the
+    /// stats of the table.
+    pub fn run<T>(
+        &mut self,
+        node: &board::Board,
+        search: impl FnOnce(&mut board::Board, &mut Self) -> error::Result<T>,
+    ) -> error::Result<T> {
+        // The table may be shared with earlier searches, so only the lookups
+        // of this search are counted.
+        let before = self.table.stats();
+        self.stats.stopwatch_start();
+        let result = search(&mut node.clone(), self);
+        self.stats.stopwatch_stop();
+        self.stats.add_table(self.table.stats().since(&before));
+        result
+    }
+
+    /// Return the current progress of the search.
+    pub fn progress(&self) -> search::Progress {
+        search::Progress {
+            min: self.window.0,
+            max: self.window.1,
+            nullwindows: self.stats.nullwindows,
+            visited: self.stats.visited,
+            elapsed: self.start.elapsed(),
+            table_count: self.table.count(),
+            table_size: self.table.size(),
+        }
+    }
+
+    /// Report the progress if the interval has passed since the last report.
+    fn report(&mut self, now: std::time::Instant) {
+        let due = self
+            .reporter
+            .as_ref()
+            .is_some_and(|reporter| now.duration_since(reporter.last) >= reporter.interval);
+        if !due {
+            return;
+        }
+
+        let progress = self.progress();
+        if let Some(reporter) = &mut self.reporter {
+            reporter.last = now;
+            (reporter.callback)(&progress);
+        }
+    }
+
+    /// Check whether the search is stopped, or the time or node limit is
+    /// reached, and stop the search if so. Also reports the progress.
+    fn check(&mut self) -> error::Result<()> {
+        let now = match self.stats.visited % CLOCK_INTERVAL == 0 {
+            true => Some(std::time::Instant::now()),
+            false => None,
+        };
+        if let Some(now) = now {
+            self.report(now);
+        }
+
+        let reason = if self
+            .limits
+            .stop
+            .as_ref()
+            .is_some_and(|stop| stop.is_stopped())
+        {
+            Some((search::Stop::Stopped, error::Error::Cancelled))
+        } else if now
+            .zip(self.deadline)
+            .is_some_and(|(now, deadline)| now >= deadline)
+        {
+            Some((search::Stop::Timeout, error::Error::Timeout))
+        } else if self
+            .limits
+            .nodes
+            .is_some_and(|nodes| self.stats.visited >= nodes)
+        {
+            Some((search::Stop::Nodes, error::Error::NodeLimit))
+        } else {
+            None
+        };
+
+        match reason {
+            Some((stop, error)) => {
+                self.stats.stop(stop);
+                Err(error)
+            }
+            None => Ok(()),
+        }
+    }
 }
 
 /// Evaluate the board from the perspective of the player onturn.
@@ -56,25 +194,25 @@ fn evaluation(
 }
 
 pub fn eval(
-    node: &board::Board,
+    node: &mut board::Board,
     mut alpha: eval::Eval,
     mut beta: eval::Eval,
-    negamax: &mut Negamax,
-) -> Result<eval::Eval, ()> {
+    negamax: &mut Negamax<'_>,
+) -> error::Result<eval::Eval> {
     debug_assert!(alpha < beta);
 
-    // Check if the timeout is reached and we should interrupt the search.
-    let recv = negamax.recv_timeout.try_recv();
-    if recv.is_ok() || recv == Err(std::sync::mpsc::TryRecvError::Disconnected) {
-        negamax.stats.timeout = true;
-        return Err(());
-    }
+    // Check if the time, node or stop limit is reached and we should
+    // interrupt the search.
+    negamax.check()?;
 
-    negamax.stats.visited += 1;
+    let depth = node.movecount() - negamax.rootcount;
+    negamax.stats.visit(depth as usize);
     let alpha_original = alpha;
 
-    // Check if we have already seen this node before.
-    if let Some(entry) = negamax.table.get(node.key()) {
+    // Check if we have already seen this node before. The node is the same
+    // again when its value is stored below, so the key is computed once.
+    let (hash, key) = (node.hash(), node.key());
+    if let Some(entry) = negamax.table.get(hash, key) {
         let table_value = entry.value.absolute(negamax.rootcount, node.movecount());
         match entry.flag {
             table::Flag::EXACT => return Ok(table_value),
@@ -96,24 +234,30 @@ pub fn eval(
         ));
     }
 
-    // Generate and sort the moves. Put cells that go to full squares up front.
-    let mut moves = node.moves();
-    moves.sort_by(|(_s1, c1), (_s2, _c2)| match node.isfull(*c1) {
-        true => std::cmp::Ordering::Greater,
-        false => std::cmp::Ordering::Less,
-    });
+    // The depth limit only stops the search if it has to look further.
+    if negamax.limits.depth.is_some_and(|limit| depth >= limit) {
+        negamax.stats.stop(search::Stop::Depth);
+        return Err(error::Error::DepthLimit);
+    }
 
     // Do the search recursive over all the child nodes.
     let mut value = eval::Eval::MIN;
+    negamax.stats.expanded += 1;
 
-    for (square, cell) in moves {
-        let mut child = node.clone();
-        child.play(square, cell);
+    for (index, mv) in node.ordered().enumerate() {
+        negamax.stats.children += 1;
+        let undo = node.play(mv.square, mv.cell);
+        let child = eval(node, -beta, -alpha, negamax);
+        node.unplay(undo);
 
-        value = std::cmp::max(value, -eval(&child, -beta, -alpha, negamax)?);
+        value = std::cmp::max(value, -child?);
 
         alpha = std::cmp::max(alpha, value);
         if alpha >= beta {
+            negamax.stats.cutoffs += 1;
+            if index == 0 {
+                negamax.stats.first_cutoffs += 1;
+            }
             break;
         }
     }
@@ -129,7 +273,7 @@ pub fn eval(
         }
     };
     let table_value = value.relative(negamax.rootcount, node.movecount());
-    negamax.table.put(node.key(), table_value, flag);
+    negamax.table.put(hash, key, table_value, flag);
 
     Ok(value)
 }
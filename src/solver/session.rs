@@ -0,0 +1,228 @@
+use super::{best, eval::all, eval::mtdf, limits, negamax, table};
+use crate::{board, error, eval, stats::search};
+
+/// The interval and the callback to report the progress of a search to.
+type Progress<'a> = (std::time::Duration, Box<dyn FnMut(&search::Progress) + 'a>);
+
+/// A solver which keeps its table between searches, so positions seen in an
+/// earlier search are found in the table. Used by long running tools which
+/// analyse related positions, like the server. The table does not know the
+/// rules of the boards, so it should be cleared before searching boards with
+/// other rules than the earlier searches.
+pub struct Session {
+    table: table::Table,
+    /// The number of searches done in this session.
+    pub searches: usize,
+    /// The number of states visited over all searches.
+    pub visited: usize,
+    /// The time spent searching over all searches.
+    pub time: std::time::Duration,
+}
+
+impl Session {
+    /// Start a session with a table of at least the given size.
+    pub fn new(size: usize) -> Self {
+        Self::with_table(table::Table::new(size))
+    }
+
+    /// Start a session with a table of the given amount of gigabytes.
+    pub fn from_gb(size: f32) -> Self {
+        Self::with_table(table::Table::from_gb(size))
+    }
+
+    fn with_table(table: table::Table) -> Self {
+        Self {
+            table,
+            searches: 0,
+            visited: 0,
+            time: std::time::Duration::ZERO,
+        }
+    }
+
+    /// Evaluate the board position within the given limits and provide stats.
+    pub fn eval(
+        &mut self,
+        node: &board::Board,
+        limits: impl Into<limits::SearchLimits>,
+    ) -> (error::Result<eval::Eval>, search::Stats) {
+        self.search(node, limits.into(), None, mtdf)
+    }
+
+    /// Return all of the best moves within the given limits and provide stats.
+    pub fn bestmoves(
+        &mut self,
+        node: &board::Board,
+        limits: impl Into<limits::SearchLimits>,
+    ) -> (error::Result<(eval::Eval, Vec<board::Move>)>, search::Stats) {
+        if node.isover().is_some() {
+            return (
+                Err(error::Error::Move(board::MoveError::GameOver)),
+                search::Stats::new(),
+            );
+        }
+        self.search(node, limits.into(), None, best::best)
+    }
+
+    /// Evaluate all the possible moves within the given limits and provide stats.
+    pub fn eval_all(
+        &mut self,
+        node: &board::Board,
+        limits: impl Into<limits::SearchLimits>,
+    ) -> (error::Result<Vec<(board::Move, eval::Eval)>>, search::Stats) {
+        if node.isover().is_some() {
+            return (
+                Err(error::Error::Move(board::MoveError::GameOver)),
+                search::Stats::new(),
+            );
+        }
+        self.search(node, limits.into(), None, all)
+    }
+
+    /// Return the evaluation and the principal variation within the given
+    /// limits and provide stats.
+    pub fn pv(
+        &mut self,
+        node: &board::Board,
+        limits: impl Into<limits::SearchLimits>,
+    ) -> (error::Result<(eval::Eval, Vec<board::Move>)>, search::Stats) {
+        if node.isover().is_some() {
+            return (
+                Err(error::Error::Move(board::MoveError::GameOver)),
+                search::Stats::new(),
+            );
+        }
+        self.search(node, limits.into(), None, best::line)
+    }
+
+    /// Return the evaluation and the principal variation within the given
+    /// limits, report the progress to the callback about every interval and
+    /// provide stats.
+    pub fn pv_with_progress<'a>(
+        &mut self,
+        node: &board::Board,
+        limits: impl Into<limits::SearchLimits>,
+        interval: std::time::Duration,
+        callback: impl FnMut(&search::Progress) + 'a,
+    ) -> (error::Result<(eval::Eval, Vec<board::Move>)>, search::Stats) {
+        if node.isover().is_some() {
+            return (
+                Err(error::Error::Move(board::MoveError::GameOver)),
+                search::Stats::new(),
+            );
+        }
+        let progress: Progress<'a> = (interval, Box::new(callback));
+        self.search(node, limits.into(), Some(progress), best::line)
+    }
+
+    /// Retrieve the stats of the table, which count over all searches.
+    pub fn table_stats(&mut self) -> crate::stats::table::Stats {
+        self.table.stats()
+    }
+
+    /// Forget everything in the table, which is needed when the rules change.
+    pub fn clear(&mut self) {
+        self.table = table::Table::new(self.table.size());
+    }
+
+    /// Lend the table to a search and take it back afterwards.
+    fn search<'a, T>(
+        &mut self,
+        node: &board::Board,
+        limits: limits::SearchLimits,
+        progress: Option<Progress<'a>>,
+        search: impl FnOnce(&mut board::Board, &mut negamax::Negamax<'a>) -> error::Result<T>,
+    ) -> (error::Result<T>, search::Stats) {
+        let table = std::mem::replace(&mut self.table, table::Table::new(0));
+        let mut negamax =
+            negamax::Negamax::with_table(limits, node.movecount(), node.onturn(), table);
+        if let Some((interval, callback)) = progress {
+            negamax = negamax.with_progress(interval, callback);
+        }
+        // The table is taken back even if the search panics, so the session
+        // can still be used by whoever catches the panic.
+        let result =
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| negamax.run(node, search)));
+        self.table = negamax.table;
+        let result = match result {
+            Ok(result) => result,
+            Err(panic) => std::panic::resume_unwind(panic),
+        };
+
+        self.searches += 1;
+        self.visited += negamax.stats.visited;
+        self.time += negamax.stats.time;
+        (result, negamax.stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test whether later searches profit from the table of earlier ones.
+    #[test]
+    fn reuse() {
+        let mut session = Session::new(1_000_000);
+        let board = board::Board::load("12104023441433032242301121").unwrap();
+
+        let (first, stats) = session.eval(&board, std::time::Duration::MAX);
+        let first = first.unwrap();
+        let (second, again) = session.eval(&board, std::time::Duration::MAX);
+        assert_eq!(second.unwrap(), first);
+        assert!(again.visited < stats.visited);
+        // Only the lookups of the search itself are counted.
+        assert!(again.table.hits > 0 && again.table.probes <= again.visited);
+
+        // The positions after the moves were already searched as well.
+        let (bestmoves, stats) = session.bestmoves(&board, std::time::Duration::MAX);
+        assert_eq!(bestmoves.unwrap().0, first);
+        assert!(stats.table.hits > 0 && stats.table.probes <= stats.visited);
+
+        assert_eq!(session.searches, 3);
+        assert!(session.table_stats().count > 0);
+        session.clear();
+        assert_eq!(session.table_stats().count, 0);
+    }
+
+    /// Test whether finished games and limits give errors.
+    #[test]
+    fn errors() {
+        let mut session = Session::new(1000);
+        let over = board::Board::load("2320212422").unwrap();
+        assert!(matches!(
+            session.bestmoves(&over, std::time::Duration::MAX).0,
+            Err(error::Error::Move(board::MoveError::GameOver))
+        ));
+        assert!(matches!(
+            session.eval_all(&over, std::time::Duration::MAX).0,
+            Err(error::Error::Move(board::MoveError::GameOver))
+        ));
+
+        let board = board::Board::load("0123").unwrap();
+        let (result, stats) = session.pv(&board, limits::SearchLimits::new().nodes(100));
+        assert!(matches!(result, Err(error::Error::NodeLimit)));
+        assert_eq!(stats.visited, 100);
+
+        // The session can still be used after a search failed.
+        assert!(session.eval(&over, std::time::Duration::MAX).0.is_ok());
+    }
+
+    /// Test whether the table is taken back when a search panics.
+    #[test]
+    fn panic() {
+        let mut session = Session::new(1000);
+        let board = board::Board::load("0123").unwrap();
+        let size = session.table_stats().size;
+
+        let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            session.search(
+                &board,
+                limits::SearchLimits::new(),
+                None,
+                |_, _| -> error::Result<()> { panic!("The search failed.") },
+            )
+        }));
+        assert!(panicked.is_err());
+        assert_eq!(session.table_stats().size, size);
+    }
+}
@@ -1,6 +1,7 @@
 use crate::{eval, stats::table};
 
-/// A fast lookup table without collision detection.
+/// A fast lookup table, the entries are placed by the hash of the board and
+/// identified by its key, so boards with the same hash are not confused.
 pub struct Table {
     table: Vec<Option<Entry>>,
     stats: table::Stats,
@@ -20,7 +21,7 @@ pub enum Flag {
 /// One entry in the table.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Entry {
-    /// A key uniquely identifying the board state, used to index the table.
+    /// A key uniquely identifying the board state, checked on retrieval.
     pub key: u64,
     /// The current evaluation of the board.
     pub value: eval::Eval,
@@ -52,38 +53,53 @@ impl Table {
         Self::new(size)
     }
 
-    fn index(&self, key: u64) -> usize {
-        (key % self.table.len() as u64) as usize
+    fn index(&self, hash: u64) -> usize {
+        (hash % self.table.len() as u64) as usize
     }
 
-    /// Put a new value with a given key inside the table.
-    pub fn put(&mut self, key: u64, value: eval::Eval, flag: Flag) {
+    /// Put a new value with a given key inside the table, at the place given
+    /// by the hash.
+    pub fn put(&mut self, hash: u64, key: u64, value: eval::Eval, flag: Flag) {
         let entry = Entry { key, value, flag };
-        let index = self.index(entry.key);
+        let index = self.index(hash);
+        match self.table[index] {
+            None => self.stats.count += 1,
+            Some(old) if old.key != key => self.stats.overwrites += 1,
+            Some(_) => (),
+        }
         self.table[index] = Some(entry);
     }
 
-    /// Retrieve the value identified by the given key, if present.
-    pub fn get(&mut self, key: u64) -> Option<Entry> {
-        if let Some(entry) = self.table[self.index(key)] {
+    /// Retrieve the value identified by the given key, if present at the
+    /// place given by the hash.
+    pub fn get(&mut self, hash: u64, key: u64) -> Option<Entry> {
+        self.stats.probes += 1;
+        if let Some(entry) = self.table[self.index(hash)] {
             if entry.key == key {
                 self.stats.hits += 1;
+                if entry.flag != Flag::EXACT {
+                    self.stats.bound_hits += 1;
+                }
                 return Some(entry);
             }
+            self.stats.collisions += 1;
         }
         None
     }
 
-    /// Retrieve the number of elements in the table.
-    fn count(&self) -> usize {
-        (0..self.table.len())
-            .filter(|&i| self.table[i].is_some())
-            .count()
+    /// Retrieve the number of elements in the table, which is kept up to date
+    /// by put() so it can be reported during a search.
+    pub fn count(&self) -> usize {
+        self.stats.count
+    }
+
+    /// Retrieve the size of the table.
+    pub fn size(&self) -> usize {
+        self.stats.size
     }
 
     /// Retrieve the stats of this table.
     pub fn stats(&mut self) -> table::Stats {
-        self.stats.count = self.count();
         self.stats.sparcity = self.stats.count as f64 / self.stats.size as f64;
         self.stats
     }
@@ -111,36 +127,36 @@ mod tests {
         let mut table = Table::new(10);
         assert_eq!(table.table.len(), 11);
 
-        assert_eq!(table.get(0), None);
-        assert_eq!(table.get(3), None);
-        assert_eq!(table.get(9), None);
+        assert_eq!(table.get(0, 0), None);
+        assert_eq!(table.get(3, 3), None);
+        assert_eq!(table.get(9, 9), None);
 
         let entry = Entry {
             key: 3,
             value: eval::Eval::MIN,
             flag: Flag::UPPERBOUND,
         };
-        table.put(entry.key, entry.value, entry.flag);
-        assert_eq!(table.get(3), Some(entry));
-        assert_eq!(table.get(14), None);
+        table.put(entry.key, entry.key, entry.value, entry.flag);
+        assert_eq!(table.get(3, 3), Some(entry));
+        assert_eq!(table.get(14, 14), None);
 
         let entry = Entry {
             key: 8,
             value: eval::Eval::MAX,
             flag: Flag::LOWERBOUND,
         };
-        table.put(entry.key, entry.value, entry.flag);
-        assert_eq!(table.get(8), Some(entry));
-        assert_eq!(table.get(30), None);
+        table.put(entry.key, entry.key, entry.value, entry.flag);
+        assert_eq!(table.get(8, 8), Some(entry));
+        assert_eq!(table.get(30, 30), None);
 
         let entry = Entry {
             key: 19,
             value: eval::Eval::MAX,
             flag: Flag::LOWERBOUND,
         };
-        table.put(entry.key, entry.value, entry.flag);
-        assert_eq!(table.get(19), Some(entry));
-        assert_eq!(table.get(8), None);
+        table.put(entry.key, entry.key, entry.value, entry.flag);
+        assert_eq!(table.get(19, 19), Some(entry));
+        assert_eq!(table.get(8, 8), None);
     }
 
     /// Test whether the size and count functions work correctly.
@@ -153,22 +169,56 @@ mod tests {
             value: eval::Eval::MIN,
             flag: Flag::UPPERBOUND,
         };
-        table.put(entry.key, entry.value, entry.flag);
+        table.put(entry.key, entry.key, entry.value, entry.flag);
 
         let entry = Entry {
             key: 8,
             value: eval::Eval::MAX,
             flag: Flag::LOWERBOUND,
         };
-        table.put(entry.key, entry.value, entry.flag);
+        table.put(entry.key, entry.key, entry.value, entry.flag);
 
         let entry = Entry {
             key: 19,
             value: eval::Eval::MAX,
             flag: Flag::LOWERBOUND,
         };
-        table.put(entry.key, entry.value, entry.flag);
+        table.put(entry.key, entry.key, entry.value, entry.flag);
 
         assert_eq!(table.count(), 2);
     }
+
+    /// Test whether the probes, hits, overwrites and collisions are counted.
+    #[test]
+    fn stats() {
+        let mut table = Table::new(10);
+        table.put(3, 3, eval::Eval::MIN, Flag::UPPERBOUND);
+        table.put(3, 3, eval::Eval::MIN, Flag::EXACT);
+        table.put(8, 8, eval::Eval::MAX, Flag::LOWERBOUND);
+        table.put(19, 19, eval::Eval::MAX, Flag::LOWERBOUND);
+
+        table.get(3, 3);
+        table.get(19, 19);
+        table.get(8, 8);
+        table.get(4, 4);
+
+        let stats = table.stats();
+        assert_eq!(stats.count, 2);
+        assert_eq!(stats.probes, 4);
+        assert_eq!(stats.hits, 2);
+        assert_eq!(stats.bound_hits, 1);
+        assert_eq!(stats.overwrites, 1);
+        assert_eq!(stats.collisions, 1);
+        assert_eq!(stats.hit_rate(), 0.5);
+    }
+
+    /// Test whether a board with the same hash but another key is not found.
+    #[test]
+    fn same_hash() {
+        let mut table = Table::new(10);
+        table.put(3, 100, eval::Eval::MAX, Flag::EXACT);
+        assert_eq!(table.get(3, 200), None);
+        assert_eq!(table.get(3, 100).map(|entry| entry.key), Some(100));
+        assert_eq!(table.stats().collisions, 1);
+    }
 }
@@ -1,19 +1,62 @@
 use super::table;
+use crate::eval;
+
+/// The reason why a search stopped before it finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Stop {
+    /// The time limit was reached.
+    Timeout,
+    /// The maximum number of visited states was reached.
+    Nodes,
+    /// The search had to look further than the maximum depth.
+    Depth,
+    /// The search was stopped with a stop handle.
+    Stopped,
+}
+
+impl std::fmt::Display for Stop {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Stop::Timeout => write!(f, "TIMEOUT"),
+            Stop::Nodes => write!(f, "NODE LIMIT"),
+            Stop::Depth => write!(f, "DEPTH LIMIT"),
+            Stop::Stopped => write!(f, "STOPPED"),
+        }
+    }
+}
 
 /// The statistics of a given search performed by the solver.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Stats {
     /// The time the solver started, used to calculate the total time.
+    #[cfg_attr(feature = "serde", serde(skip))]
     time_start: Option<std::time::Instant>,
 
     /// The amount of time the solver took in total.
     pub time: std::time::Duration,
-    /// If the timeout was reached.
-    pub timeout: bool,
+    /// Why the search stopped early, if it did.
+    pub stop: Option<Stop>,
 
     /// The number of null window searches iterations.
     pub nullwindows: usize,
     /// The number of states that were evaluated.
     pub visited: usize,
+    /// The number of states visited at each depth from the root.
+    pub depths: Vec<usize>,
+    /// The number of states of which the child states were searched.
+    pub expanded: usize,
+    /// The number of child states searched.
+    pub children: usize,
+    /// The number of searches cut off after a move reached beta.
+    pub cutoffs: usize,
+    /// The number of cutoffs made by the first move searched.
+    pub first_cutoffs: usize,
 
     /// Table information.
     pub table: table::Stats,
@@ -24,16 +67,94 @@ impl Stats {
         Self {
             time_start: None,
             time: std::time::Duration::ZERO,
-            timeout: false,
+            stop: None,
             nullwindows: 0,
             visited: 0,
+            depths: Vec::new(),
+            expanded: 0,
+            children: 0,
+            cutoffs: 0,
+            first_cutoffs: 0,
             table: table::Stats::new(),
         }
     }
 
-    /// A timeout has been reached.
-    pub fn timeout(&mut self) {
-        self.timeout = true;
+    /// A state at the given depth from the root is visited.
+    pub fn visit(&mut self, depth: usize) {
+        self.visited += 1;
+        if self.depths.len() <= depth {
+            self.depths.resize(depth + 1, 0);
+        }
+        self.depths[depth] += 1;
+    }
+
+    /// The fraction of the cutoffs made by the first move, the higher the
+    /// better the move ordering.
+    pub fn first_cutoff_rate(&self) -> f64 {
+        match self.cutoffs {
+            0 => 0.0,
+            cutoffs => self.first_cutoffs as f64 / cutoffs as f64,
+        }
+    }
+
+    /// The average number of child states searched per expanded state.
+    pub fn average_children(&self) -> f64 {
+        match self.expanded {
+            0 => 0.0,
+            expanded => self.children as f64 / expanded as f64,
+        }
+    }
+
+    /// The effective branching factor, the branching factor b of a uniform
+    /// tree as deep as the deepest visited state which has about as many
+    /// states as were visited, i.e. visited^(1 / depth).
+    pub fn effective_branching_factor(&self) -> f64 {
+        match self.depths.len() {
+            0 | 1 => 0.0,
+            length => (self.visited as f64).powf(1.0 / (length - 1) as f64),
+        }
+    }
+
+    /// The names of the columns of to_csv().
+    pub fn csv_header() -> &'static str {
+        concat!(
+            "time_ms,stop,nullwindows,visited,expanded,children,cutoffs,first_cutoffs,",
+            "branching_factor,",
+            "table_probes,table_hits,table_bound_hits,table_overwrites,table_collisions,",
+            "table_count,table_size,depths"
+        )
+    }
+
+    /// Format the stats as a line of comma separated values, the depth
+    /// profile is separated by semicolons.
+    pub fn to_csv(&self) -> String {
+        let stop = self.stop.map_or(String::new(), |stop| stop.to_string());
+        let depths: Vec<String> = self.depths.iter().map(|count| count.to_string()).collect();
+        format!(
+            "{},{},{},{},{},{},{},{},{:.4},{},{},{},{},{},{},{},{}",
+            self.time.as_millis(),
+            stop,
+            self.nullwindows,
+            self.visited,
+            self.expanded,
+            self.children,
+            self.cutoffs,
+            self.first_cutoffs,
+            self.effective_branching_factor(),
+            self.table.probes,
+            self.table.hits,
+            self.table.bound_hits,
+            self.table.overwrites,
+            self.table.collisions,
+            self.table.count,
+            self.table.size,
+            depths.join(";"),
+        )
+    }
+
+    /// The search stopped early for the given reason.
+    pub fn stop(&mut self, reason: Stop) {
+        self.stop = Some(reason);
     }
 
     /// Add the table stats.
@@ -58,19 +179,115 @@ impl Stats {
 impl std::fmt::Display for Stats {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "search time: {}ms", self.time.as_millis())?;
-        match self.timeout {
-            true => write!(f, " TIMEOUT\n")?,
-            false => write!(f, "\n")?,
+        match self.stop {
+            Some(reason) => writeln!(f, " {}", reason)?,
+            None => writeln!(f)?,
         }
 
         if self.nullwindows > 0 {
-            write!(f, "null windows: {}\n", self.nullwindows)?;
+            writeln!(f, "null windows: {}", self.nullwindows)?;
+        }
+
+        let depths: Vec<String> = self.depths.iter().map(|count| count.to_string()).collect();
+        write!(
+            f,
+            concat!(
+                "states visited: {}\n",
+                "states per depth: {}\n",
+                "cutoffs: {} (first move {:.2}%)\n",
+                "average children: {:.2}\n",
+                "effective branching factor: {:.2}\n",
+                "\n{}\n",
+            ),
+            self.visited,
+            depths.join(" "),
+            self.cutoffs,
+            self.first_cutoff_rate() * 100.0,
+            self.average_children(),
+            self.effective_branching_factor(),
+            self.table,
+        )
+    }
+}
+
+/// A snapshot of a running search, reported periodically while searching.
+#[derive(Debug, Clone, Copy)]
+pub struct Progress {
+    /// The lowest value the evaluation can still have.
+    pub min: eval::Eval,
+    /// The highest value the evaluation can still have.
+    pub max: eval::Eval,
+    /// The number of null window searches started so far.
+    pub nullwindows: usize,
+    /// The number of states visited so far.
+    pub visited: usize,
+    /// The time since the search started.
+    pub elapsed: std::time::Duration,
+    /// The number of elements in the table.
+    pub table_count: usize,
+    /// The size of the table.
+    pub table_size: usize,
+}
+
+impl Progress {
+    /// The number of states visited per second so far.
+    pub fn visited_per_second(&self) -> usize {
+        match self.elapsed.as_secs_f64() {
+            elapsed if elapsed > 0.0 => (self.visited as f64 / elapsed) as usize,
+            _ => 0,
         }
+    }
+
+    /// The fraction of the table which is filled.
+    pub fn fill(&self) -> f64 {
+        self.table_count as f64 / self.table_size as f64
+    }
+}
 
+impl std::fmt::Display for Progress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            concat!("states visited: {}\n", "\n{}\n",),
-            self.visited, self.table,
+            "{}ms: [{}, {}] windows {} visited {} ({}/s) table {:.2}%",
+            self.elapsed.as_millis(),
+            self.min,
+            self.max,
+            self.nullwindows,
+            self.visited,
+            self.visited_per_second(),
+            self.fill() * 100.0,
         )
     }
 }
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+
+    /// Test whether the stats of a search and its table are serialized and
+    /// read again.
+    #[test]
+    fn serde() {
+        let mut stats = Stats::new();
+        stats.stopwatch_start();
+        stats.visit(0);
+        stats.visit(2);
+        stats.stop(Stop::Nodes);
+        stats.stopwatch_stop();
+        stats.table.size = 101;
+        stats.table.probes = 7;
+
+        let json = serde_json::to_string(&stats).unwrap();
+        let read: Stats = serde_json::from_str(&json).unwrap();
+        assert_eq!(read.to_csv(), stats.to_csv());
+        assert_eq!(read.stop, Some(Stop::Nodes));
+        assert_eq!(read.time, stats.time);
+        assert_eq!(read.table.size, 101);
+        assert_eq!(read.table.probes, 7);
+
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["depths"], serde_json::json!([1, 0, 1]));
+        assert_eq!(value["stop"], "nodes");
+        assert!(value.get("time_start").is_none());
+    }
+}
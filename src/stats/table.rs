@@ -1,5 +1,6 @@
 /// Statistics on a table.
 #[derive(Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Stats {
     /// Time it took to create the table.
     pub time: std::time::Duration,
@@ -9,8 +10,16 @@ pub struct Stats {
     pub count: usize,
     /// #elements / size
     pub sparcity: f64,
+    /// Number of lookups made.
+    pub probes: usize,
     /// Number of hits made.
     pub hits: usize,
+    /// Number of hits which only gave a bound instead of an exact value.
+    pub bound_hits: usize,
+    /// Number of entries replaced by an entry with another key.
+    pub overwrites: usize,
+    /// Number of lookups which found an entry with another key.
+    pub collisions: usize,
 }
 
 impl Stats {
@@ -20,7 +29,32 @@ impl Stats {
             size: 0,
             count: 0,
             sparcity: 0.0,
+            probes: 0,
             hits: 0,
+            bound_hits: 0,
+            overwrites: 0,
+            collisions: 0,
+        }
+    }
+
+    /// The counters of the lookups and writes made since the earlier stats of
+    /// the same table, the size and count are those of now.
+    pub fn since(&self, earlier: &Self) -> Self {
+        Self {
+            probes: self.probes - earlier.probes,
+            hits: self.hits - earlier.hits,
+            bound_hits: self.bound_hits - earlier.bound_hits,
+            overwrites: self.overwrites - earlier.overwrites,
+            collisions: self.collisions - earlier.collisions,
+            ..*self
+        }
+    }
+
+    /// The fraction of the lookups which were hits.
+    pub fn hit_rate(&self) -> f64 {
+        match self.probes {
+            0 => 0.0,
+            probes => self.hits as f64 / probes as f64,
         }
     }
 }
@@ -31,11 +65,18 @@ impl std::fmt::Display for Stats {
             f,
             concat!(
                 "table creation time: {}ms\n",
-                "table hits: {}\n",
+                "table probes: {}\n",
+                "table hits: {} ({:.2}%), bounds only: {}\n",
+                "overwrites: {}, collisions: {}\n",
                 "count / size = {} / {} = {:.6}\n",
             ),
             self.time.as_millis(),
+            self.probes,
             self.hits,
+            self.hit_rate() * 100.0,
+            self.bound_hits,
+            self.overwrites,
+            self.collisions,
             self.count,
             self.size,
             self.sparcity,
@@ -0,0 +1,228 @@
+use crate::{board, player};
+use std::collections::HashMap;
+
+/// The reason why two boards with the same key are not the same position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reason {
+    /// The boards have different moves.
+    Moves,
+    /// The boards differ in whether and how the game is over.
+    Isover,
+    /// The number of stones left differs.
+    Stones,
+    /// The given move leads to boards with different keys.
+    Child(board::Move),
+}
+
+/// Two boards which have the same key, but a different future.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Collision {
+    /// The key of both boards.
+    pub key: u64,
+    /// The position notation of the two boards, see Board::to_fen().
+    pub first: String,
+    pub second: String,
+    pub reason: Reason,
+}
+
+impl Collision {
+    fn new(first: &board::Board, second: &board::Board, reason: Reason) -> Self {
+        Self {
+            key: first.key(),
+            first: first.to_fen(),
+            second: second.to_fen(),
+            reason,
+        }
+    }
+}
+
+impl std::fmt::Display for Collision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reason = match self.reason {
+            Reason::Moves => String::from("the moves differ"),
+            Reason::Isover => String::from("the result differs"),
+            Reason::Stones => String::from("the stones left differ"),
+            Reason::Child(mv) => format!("the keys after move {} differ", mv),
+        };
+        write!(
+            f,
+            "key {} collides, {}:\n  {}\n  {}",
+            self.key, reason, self.first, self.second
+        )
+    }
+}
+
+/// The numbers of boards checked by verify().
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Summary {
+    /// The number of boards visited.
+    pub visited: usize,
+    /// The number of different keys found.
+    pub keys: usize,
+    /// The number of boards compared to an earlier board with the same key.
+    pub compared: usize,
+}
+
+/// Compare two boards with the same key and return why they differ, if they do.
+/// If all the boards with the same key have the same moves, leading to boards
+/// with the same keys again, they also have the same game-theoretic future.
+pub fn compare(first: &board::Board, second: &board::Board) -> Option<Reason> {
+    if compare_stones(first, second) {
+        return Some(Reason::Stones);
+    }
+    if first.isover() != second.isover() {
+        return Some(Reason::Isover);
+    }
+    if first.isover().is_some() {
+        return None;
+    }
+
+    let moves = first.moves();
+    if moves != second.moves() {
+        return Some(Reason::Moves);
+    }
+
+    let (mut first, mut second) = (first.clone(), second.clone());
+    for mv in moves {
+        let (undo_first, undo_second) = (
+            first.play(mv.square, mv.cell),
+            second.play(mv.square, mv.cell),
+        );
+        if first.key() != second.key() {
+            return Some(Reason::Child(mv));
+        }
+        first.unplay(undo_first);
+        second.unplay(undo_second);
+    }
+    None
+}
+
+/// The boards seen so far by their key, with the depth left after them.
+struct Verify {
+    boards: HashMap<u64, (board::Board, usize)>,
+    summary: Summary,
+}
+
+/// Walk through all the boards within the given depth and check the boards
+/// against the earlier boards with the same key.
+fn walk(board: &mut board::Board, depth: usize, verify: &mut Verify) -> Result<(), Collision> {
+    verify.summary.visited += 1;
+
+    // The stones are not in the key, check that they can be recovered from it.
+    if compare_stones(board, &board::Board::from_key(board.key())) {
+        let from_key = board::Board::from_key(board.key());
+        return Err(Collision::new(board, &from_key, Reason::Stones));
+    }
+
+    match verify.boards.get_mut(&board.key()) {
+        Some((earlier, left)) => {
+            verify.summary.compared += 1;
+            if let Some(reason) = compare(earlier, board) {
+                return Err(Collision::new(earlier, board, reason));
+            }
+
+            // Only walk the subtree again if it was not walked as deep before.
+            if *left >= depth {
+                return Ok(());
+            }
+            *left = depth;
+        }
+        None => {
+            verify.boards.insert(board.key(), (board.clone(), depth));
+        }
+    }
+
+    if depth == 0 || board.isover().is_some() {
+        return Ok(());
+    }
+
+    // Also try all the first moves, as Board::moves() only gives one per symmetry.
+    let moves: Vec<board::Move> = match board.square() {
+        None => (0..25)
+            .map(|index| board::Move::new(index / 5, index % 5))
+            .filter(|mv| board.canplay(mv.square, mv.cell))
+            .collect(),
+        Some(_) => board.moves(),
+    };
+
+    for mv in moves {
+        let undo = board.play(mv.square, mv.cell);
+        let result = walk(board, depth - 1, verify);
+        board.unplay(undo);
+        result?;
+    }
+    Ok(())
+}
+
+/// Return whether the number of stones left of the boards differ.
+fn compare_stones(first: &board::Board, second: &board::Board) -> bool {
+    [player::Players::Player1, player::Players::Player2]
+        .iter()
+        .any(|&player| first.stones(player) != second.stones(player))
+}
+
+/// Check whether all the boards within the given depth from the given board
+/// which have the same key, are the same position. Returns the first collision
+/// found if there is any.
+pub fn verify(board: &board::Board, depth: usize) -> Result<Summary, Collision> {
+    let mut verify = Verify {
+        boards: HashMap::new(),
+        summary: Summary::default(),
+    };
+    walk(&mut board.clone(), depth, &mut verify)?;
+
+    verify.summary.keys = verify.boards.len();
+    Ok(verify.summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test whether no collisions are found from the empty board.
+    #[test]
+    fn verify_empty() {
+        let summary = verify(&board::Board::new(), 7).unwrap();
+        assert!(summary.compared > 0);
+        assert_eq!(summary.visited, summary.keys + summary.compared);
+    }
+
+    /// Test whether no collisions are found in positions with takes and games
+    /// which end.
+    #[test]
+    fn verify_takes() {
+        for moves in [
+            "221400203101122",
+            "0123432100304022",
+            "20033102212432011410302234201",
+        ] {
+            let board = board::Board::load(moves).unwrap();
+            assert!(verify(&board, 8).is_ok(), "{}", moves);
+        }
+    }
+
+    /// Test whether a board created from the key has the same moves. The
+    /// movecount is not in the key and isover() does not check boards with
+    /// less than 9 moves, so only compare boards which are not over.
+    #[test]
+    fn compare_from_key() {
+        for _ in 0..1000 {
+            let board = board::Board::random();
+            if board.isover().is_some() {
+                continue;
+            }
+            assert_eq!(compare(&board, &board::Board::from_key(board.key())), None);
+        }
+    }
+
+    /// Test whether different positions are detected.
+    #[test]
+    fn compare_different() {
+        let board1 = board::Board::load("00").unwrap();
+        let board2 = board::Board::load("01").unwrap();
+        assert_eq!(compare(&board1, &board2), Some(Reason::Moves));
+
+        let board3 = board::Board::load("0010").unwrap();
+        assert_eq!(compare(&board1, &board3), Some(Reason::Stones));
+    }
+}